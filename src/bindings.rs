@@ -0,0 +1,397 @@
+use crate::paths;
+use minifb::{Key, KeyRepeat, Window};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Things the player can do, independent of which keys do them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Fire,
+    NextWeapon,
+    Bomb,
+    Pause,
+    Quit,
+}
+
+pub const ACTIONS: [Action; 9] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::Fire,
+    Action::NextWeapon,
+    Action::Bomb,
+    Action::Pause,
+    Action::Quit,
+];
+
+// More than this per action doesn't fit on the controls screen
+pub const MAX_KEYS_PER_ACTION: usize = 4;
+
+impl Action {
+    // Name used in the bindings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::Fire => "fire",
+            Action::NextWeapon => "next_weapon",
+            Action::Bomb => "bomb",
+            Action::Pause => "pause",
+            Action::Quit => "quit",
+        }
+    }
+
+    // Name shown on the controls screen
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::MoveUp => "MOVE UP",
+            Action::MoveDown => "MOVE DOWN",
+            Action::Fire => "FIRE",
+            Action::NextWeapon => "NEXT WEAPON",
+            Action::Bomb => "BOMB",
+            Action::Pause => "PAUSE",
+            Action::Quit => "QUIT",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().copied().find(|action| action.name() == name)
+    }
+}
+
+// Every key that can be bound; names in the file are the variant names, e.g. "Left" or "NumPad4"
+const BINDABLE_KEYS: [Key; 106] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Up,
+    Key::Apostrophe,
+    Key::Backquote,
+    Key::Backslash,
+    Key::Comma,
+    Key::Equal,
+    Key::LeftBracket,
+    Key::Minus,
+    Key::Period,
+    Key::RightBracket,
+    Key::Semicolon,
+    Key::Slash,
+    Key::Backspace,
+    Key::Delete,
+    Key::End,
+    Key::Enter,
+    Key::Escape,
+    Key::Home,
+    Key::Insert,
+    Key::Menu,
+    Key::PageDown,
+    Key::PageUp,
+    Key::Pause,
+    Key::Space,
+    Key::Tab,
+    Key::NumLock,
+    Key::CapsLock,
+    Key::ScrollLock,
+    Key::LeftShift,
+    Key::RightShift,
+    Key::LeftCtrl,
+    Key::RightCtrl,
+    Key::NumPad0,
+    Key::NumPad1,
+    Key::NumPad2,
+    Key::NumPad3,
+    Key::NumPad4,
+    Key::NumPad5,
+    Key::NumPad6,
+    Key::NumPad7,
+    Key::NumPad8,
+    Key::NumPad9,
+    Key::NumPadDot,
+    Key::NumPadSlash,
+    Key::NumPadAsterisk,
+    Key::NumPadMinus,
+    Key::NumPadPlus,
+    Key::NumPadEnter,
+    Key::LeftAlt,
+    Key::RightAlt,
+    Key::LeftSuper,
+    Key::RightSuper,
+];
+
+pub fn key_name(key: Key) -> String {
+    format!("{key:?}")
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| key_name(*key).eq_ignore_ascii_case(name))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bindings {
+    // Indexed by the action's position in ACTIONS
+    keys: [Vec<Key>; ACTIONS.len()],
+}
+
+impl Default for Bindings {
+    // Arrows and WASD both move, so either hand can steer
+    fn default() -> Self {
+        Self {
+            keys: [
+                vec![Key::Left, Key::A],
+                vec![Key::Right, Key::D],
+                vec![Key::Up, Key::W],
+                vec![Key::Down, Key::S],
+                vec![Key::Space],
+                vec![Key::E, Key::Tab],
+                vec![Key::B, Key::LeftShift],
+                vec![Key::P],
+                vec![Key::Escape],
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    pub fn keys(&self, action: Action) -> &[Key] {
+        &self.keys[action as usize]
+    }
+
+    pub fn is_down(&self, window: &Window, action: Action) -> bool {
+        self.keys(action).iter().any(|key| window.is_key_down(*key))
+    }
+
+    pub fn is_pressed(&self, window: &Window, action: Action) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|key| window.is_key_pressed(*key, KeyRepeat::No))
+    }
+
+    // Adds `key` to `action`, taking it away from whatever action had it before so one
+    // key never does two things
+    pub fn bind(&mut self, action: Action, key: Key) {
+        for keys in self.keys.iter_mut() {
+            keys.retain(|k| *k != key);
+        }
+        let keys: &mut Vec<Key> = &mut self.keys[action as usize];
+        keys.push(key);
+        if keys.len() > MAX_KEYS_PER_ACTION {
+            keys.remove(0);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.keys[action as usize].clear();
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text: String = String::from("# eagle key bindings: action = key, key, ...\n");
+        for action in ACTIONS {
+            let names: Vec<String> = self.keys(action).iter().map(|k| key_name(*k)).collect();
+            text.push_str(&format!("{} = {}\n", action.name(), names.join(", ")));
+        }
+        text
+    }
+
+    // Starts from the defaults and applies every line it understands. Problems are
+    // returned as warnings rather than errors so one typo doesn't lose the whole file.
+    pub fn from_text(text: &str) -> (Bindings, Vec<String>) {
+        let mut bindings: Bindings = Bindings::default();
+        let mut warnings: Vec<String> = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((name, keys)) = line.split_once('=') else {
+                warnings.push(format!("line {}: expected 'action = keys'", number + 1));
+                continue;
+            };
+            let Some(action) = Action::from_name(name.trim()) else {
+                warnings.push(format!(
+                    "line {}: unknown action '{}'",
+                    number + 1,
+                    name.trim()
+                ));
+                continue;
+            };
+
+            let mut parsed: Vec<Key> = Vec::new();
+            for key in keys.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                match parse_key(key) {
+                    Some(key) if !parsed.contains(&key) => parsed.push(key),
+                    Some(_) => {}
+                    None => warnings.push(format!("line {}: unknown key '{key}'", number + 1)),
+                }
+            }
+            parsed.truncate(MAX_KEYS_PER_ACTION);
+            // "fire =" on its own deliberately leaves the action unbound
+            if parsed.is_empty() && !keys.trim().is_empty() {
+                warnings.push(format!(
+                    "line {}: no usable keys for {}, keeping the default",
+                    number + 1,
+                    action.name()
+                ));
+                continue;
+            }
+            bindings.keys[action as usize] = parsed;
+        }
+
+        (bindings, warnings)
+    }
+
+    // A missing file means the defaults
+    pub fn load(path: &Path) -> Result<(Bindings, Vec<String>), String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Bindings::from_text(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok((Bindings::default(), Vec::new()))
+            }
+            Err(e) => Err(format!("Could not read bindings {}: {e}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+        }
+        fs::write(path, self.to_text())
+            .map_err(|e| format!("Could not write bindings {}: {e}", path.display()))
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("eagle").join("bindings.txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_cover_every_action() {
+        let bindings = Bindings::default();
+        for action in ACTIONS {
+            assert!(!bindings.keys(action).is_empty());
+        }
+        assert_eq!(bindings.keys(Action::MoveLeft), &[Key::Left, Key::A]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Fire, Key::NumPad0);
+        bindings.bind(Action::MoveLeft, Key::Q);
+        let (parsed, warnings) = Bindings::from_text(&bindings.to_text());
+        assert_eq!(parsed, bindings);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_azerty_file() {
+        let text = "# ZQSD for AZERTY keyboards\n\
+                    move_up = Z, up\n\
+                    move_left = Q, Left\n\
+                    move_down = s, Down\n";
+        let (bindings, warnings) = Bindings::from_text(text);
+        assert!(warnings.is_empty());
+        assert_eq!(bindings.keys(Action::MoveUp), &[Key::Z, Key::Up]);
+        assert_eq!(bindings.keys(Action::MoveLeft), &[Key::Q, Key::Left]);
+        assert_eq!(bindings.keys(Action::MoveDown), &[Key::S, Key::Down]);
+        // Untouched actions keep their defaults
+        assert_eq!(bindings.keys(Action::Fire), &[Key::Space]);
+    }
+
+    #[test]
+    fn test_bad_lines_warn_and_keep_defaults() {
+        let text = "fire = Banana\njump = Space\nnonsense\nquit = Escape, Bogus";
+        let (bindings, warnings) = Bindings::from_text(text);
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(bindings.keys(Action::Fire), &[Key::Space]);
+        assert_eq!(bindings.keys(Action::Quit), &[Key::Escape]);
+    }
+
+    #[test]
+    fn test_bind_moves_key_between_actions() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Fire, Key::A);
+        assert_eq!(bindings.keys(Action::Fire), &[Key::Space, Key::A]);
+        assert_eq!(bindings.keys(Action::MoveLeft), &[Key::Left]);
+
+        for key in [Key::B, Key::C, Key::E] {
+            bindings.bind(Action::Fire, key);
+        }
+        assert_eq!(
+            bindings.keys(Action::Fire),
+            &[Key::A, Key::B, Key::C, Key::E]
+        );
+
+        bindings.clear(Action::Fire);
+        assert!(bindings.keys(Action::Fire).is_empty());
+        assert_eq!(Bindings::from_text(&bindings.to_text()).0, bindings);
+    }
+}
@@ -0,0 +1,469 @@
+use crate::levels::Formation;
+use crate::simple_random::SimpleRng;
+use crate::space_objects::{BigStar, StarKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BossKind {
+    // Four weak points in a diamond; sends rings of stars and sweeps a single beam
+    Warden,
+    // Six weak points round its rim; sends homing stars and splitters, and two beams at the end
+    Hive,
+}
+
+pub const BOSS_KINDS: [BossKind; 2] = [BossKind::Warden, BossKind::Hive];
+
+// Its phases: each starts when what's left of its health drops to a third less
+pub const PHASES: usize = 3;
+
+// Depth it comes in from and hovers at, how fast it gets there and how high it sits
+const ENTRY_DEPTH: f32 = 5.0;
+const HOVER_DEPTH: f32 = 2.0;
+const ENTRY_SPEED: f32 = 1.0;
+const HOVER_Y: f32 = -0.8;
+// How far it sways from side to side, and how quickly in each phase, in radians per second
+const SWAY: f32 = 0.5;
+const SWAY_RATE: [f32; PHASES] = [0.5, 0.8, 1.2];
+// Size of the hull and of each weak point, in the same units as x and y
+const HULL_RADIUS: f32 = 0.35;
+const WEAK_POINT_RADIUS: f32 = 0.06;
+// Seconds it can't be hurt after moving on to a new phase, and before its next attack then
+const PHASE_SHIELD: f32 = 1.0;
+const PHASE_PAUSE: f32 = 1.5;
+// Seconds between attacks in each phase
+const ATTACK_INTERVAL: [f32; PHASES] = [3.5, 3.0, 2.0];
+// A beam shows as a thin warning line first, then burns as it sweeps from one side to the other,
+// up to BEAM_SWEEP radians either side of straight down
+const BEAM_WARNING: f32 = 0.8;
+const BEAM_LIFE: f32 = 2.0;
+const BEAM_SWEEP: f32 = 0.9;
+// Pixels either side of its line that a burning beam reaches
+pub const BEAM_WIDTH: f32 = 8.0;
+
+impl BossKind {
+    // The lowercase name, as written in level files
+    pub fn from_name(name: &str) -> Option<BossKind> {
+        match name {
+            "warden" => Some(BossKind::Warden),
+            "hive" => Some(BossKind::Hive),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BossKind::Warden => "WARDEN",
+            BossKind::Hive => "HIVE",
+        }
+    }
+
+    pub fn color(self) -> u32 {
+        match self {
+            BossKind::Warden => 0x5566AA,
+            BossKind::Hive => 0x886633,
+        }
+    }
+
+    // Where its weak points sit on the hull, and the shots each takes
+    fn weak_points(self) -> (Vec<(f32, f32)>, u8) {
+        match self {
+            BossKind::Warden => (vec![(0.0, -0.2), (0.2, 0.0), (0.0, 0.2), (-0.2, 0.0)], 8),
+            BossKind::Hive => (
+                (0..6)
+                    .map(|i| {
+                        let angle: f32 = i as f32 * std::f32::consts::TAU / 6.0;
+                        (angle.cos() * 0.24, angle.sin() * 0.24)
+                    })
+                    .collect(),
+                6,
+            ),
+        }
+    }
+
+    // The stars it sends in one volley during `phase`
+    fn volley(self, phase: usize) -> (Formation, usize, StarKind) {
+        match (self, phase) {
+            (BossKind::Warden, 0) => (Formation::Ring, 5, StarKind::Normal),
+            (BossKind::Warden, 1) => (Formation::Vee, 5, StarKind::Drifter),
+            (BossKind::Warden, _) => (Formation::Ring, 4, StarKind::Armored),
+            (BossKind::Hive, 0) => (Formation::Line, 3, StarKind::Splitter),
+            (BossKind::Hive, 1) => (Formation::Line, 3, StarKind::Homing),
+            (BossKind::Hive, _) => (Formation::Scatter, 4, StarKind::Homing),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeakPoint {
+    // Offset from the middle of the hull
+    pub dx: f32,
+    pub dy: f32,
+    pub health: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Beam {
+    // Radians from straight down, positive to the right
+    pub angle: f32,
+    // Radians per second it turns while burning
+    spin: f32,
+    // Seconds of warning left, then of burning
+    pub warning: f32,
+    pub life: f32,
+    // Already burned the ship; a beam only does that once
+    burned: bool,
+}
+
+impl Beam {
+    // Sweeps from one side to the other, starting on the left if `from_left`
+    pub fn new(from_left: bool) -> Self {
+        let side: f32 = if from_left { -1.0 } else { 1.0 };
+        Self {
+            angle: side * BEAM_SWEEP,
+            spin: -side * 2.0 * BEAM_SWEEP / BEAM_LIFE,
+            warning: BEAM_WARNING,
+            life: BEAM_LIFE,
+            burned: false,
+        }
+    }
+
+    pub fn is_burning(&self) -> bool {
+        self.warning <= 0.0 && self.life > 0.0
+    }
+
+    fn update(&mut self, dt: f32) {
+        if self.warning > 0.0 {
+            self.warning -= dt;
+        } else {
+            self.life -= dt;
+            self.angle += self.spin * dt;
+        }
+    }
+
+    // Unit vector along the beam in screen space
+    pub fn direction(&self) -> (f32, f32) {
+        (self.angle.sin(), self.angle.cos())
+    }
+}
+
+// What a hit on a weak point did
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Damage {
+    // Took it without anything giving way, or shrugged it off while shielded
+    Dented,
+    PointDestroyed,
+    NextPhase,
+    Defeated,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boss {
+    pub kind: BossKind,
+    // Position in the same space as the big stars
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub weak_points: Vec<WeakPoint>,
+    max_health: u32,
+    pub phase: usize,
+    pub beams: Vec<Beam>,
+    // Radians into its sway
+    sway: f32,
+    // Seconds until its next attack, and attacks made so far
+    attack_timer: f32,
+    attacks: usize,
+    // Seconds left of being unhurtable after a phase change, and of the flash after a hit
+    pub shielded: f32,
+    pub flash: f32,
+}
+
+impl Boss {
+    pub fn new(kind: BossKind) -> Self {
+        let (offsets, toughness) = kind.weak_points();
+        let weak_points: Vec<WeakPoint> = offsets
+            .into_iter()
+            .map(|(dx, dy)| WeakPoint {
+                dx,
+                dy,
+                health: toughness,
+            })
+            .collect();
+        Self {
+            kind,
+            x: 0.0,
+            y: HOVER_Y,
+            z: ENTRY_DEPTH,
+            max_health: weak_points.len() as u32 * toughness as u32,
+            weak_points,
+            phase: 0,
+            beams: Vec::new(),
+            sway: 0.0,
+            attack_timer: ATTACK_INTERVAL[0],
+            attacks: 0,
+            shielded: 0.0,
+            flash: 0.0,
+        }
+    }
+
+    pub fn health(&self) -> u32 {
+        self.weak_points.iter().map(|p| p.health as u32).sum()
+    }
+
+    // 1.0 at full health, down to 0.0 when beaten
+    pub fn health_fraction(&self) -> f32 {
+        self.health() as f32 / self.max_health as f32
+    }
+
+    // Still coming in from the distance
+    pub fn is_arriving(&self) -> bool {
+        self.z > HOVER_DEPTH
+    }
+
+    pub fn is_vulnerable(&self) -> bool {
+        !self.is_arriving() && self.shielded <= 0.0
+    }
+
+    // One tick of movement and attacks, returning any stars it sent
+    pub fn update(&mut self, dt: f32, rng: &mut SimpleRng) -> Vec<BigStar> {
+        self.flash = (self.flash - dt).max(0.0);
+        self.shielded = (self.shielded - dt).max(0.0);
+        if self.is_arriving() {
+            self.z = (self.z - ENTRY_SPEED * dt).max(HOVER_DEPTH);
+            return Vec::new();
+        }
+
+        self.sway += SWAY_RATE[self.phase] * dt;
+        self.x = SWAY * self.sway.sin();
+        self.y = HOVER_Y + 0.1 * (self.sway * 1.3).sin();
+
+        for beam in self.beams.iter_mut() {
+            beam.update(dt);
+        }
+        self.beams.retain(|beam| beam.life > 0.0);
+
+        self.attack_timer -= dt;
+        if self.attack_timer > 0.0 {
+            return Vec::new();
+        }
+        self.attack_timer = ATTACK_INTERVAL[self.phase];
+        self.attacks += 1;
+
+        // From the second phase on, every other attack is a beam
+        if self.phase > 0 && self.attacks.is_multiple_of(2) {
+            let from_left: bool = rng.random_range_u32(0..2) == 0;
+            self.beams.push(Beam::new(from_left));
+            if self.kind == BossKind::Hive && self.phase == PHASES - 1 {
+                self.beams.push(Beam::new(!from_left));
+            }
+            return Vec::new();
+        }
+
+        let (formation, count, kind) = self.kind.volley(self.phase);
+        formation
+            .positions(count, (self.x, self.y, self.z - 0.2), rng)
+            .into_iter()
+            .map(|(x, y, z)| BigStar::launch(kind, x, y, z, rng))
+            .collect()
+    }
+
+    // Takes `damage` shots' worth off weak point `index`
+    pub fn damage(&mut self, index: usize, damage: u8) -> Damage {
+        if !self.is_vulnerable() {
+            return Damage::Dented;
+        }
+        let point: &mut WeakPoint = &mut self.weak_points[index];
+        point.health = point.health.saturating_sub(damage);
+        let destroyed: bool = point.health == 0;
+        self.flash = 0.1;
+
+        let health: u32 = self.health();
+        let phase: usize = if health * 3 <= self.max_health {
+            2
+        } else if health * 3 <= self.max_health * 2 {
+            1
+        } else {
+            0
+        };
+        if health == 0 {
+            Damage::Defeated
+        } else if phase > self.phase {
+            self.phase = phase;
+            self.shielded = PHASE_SHIELD;
+            self.attack_timer = PHASE_PAUSE;
+            Damage::NextPhase
+        } else if destroyed {
+            Damage::PointDestroyed
+        } else {
+            Damage::Dented
+        }
+    }
+
+    // Where it appears on a width x height screen
+    pub fn project(&self, width: usize, height: usize) -> (f32, f32) {
+        (
+            self.x / self.z * width as f32 / 2.0 + width as f32 / 2.0,
+            self.y / self.z * height as f32 / 2.0 + height as f32 / 2.0,
+        )
+    }
+
+    // Radius of the hull on a screen `width` pixels wide
+    pub fn hull_radius(&self, width: usize) -> f32 {
+        HULL_RADIUS / self.z * width as f32 / 2.0
+    }
+
+    // Shots each weak point takes when new
+    pub fn toughness(&self) -> u8 {
+        (self.max_health / self.weak_points.len() as u32) as u8
+    }
+
+    // Where a weak point appears on a width x height screen
+    pub fn weak_point_position(
+        &self,
+        point: &WeakPoint,
+        width: usize,
+        height: usize,
+    ) -> (f32, f32) {
+        (
+            (self.x + point.dx) / self.z * width as f32 / 2.0 + width as f32 / 2.0,
+            (self.y + point.dy) / self.z * height as f32 / 2.0 + height as f32 / 2.0,
+        )
+    }
+
+    pub fn weak_point_radius(&self, width: usize) -> f32 {
+        WEAK_POINT_RADIUS / self.z * width as f32 / 2.0
+    }
+
+    // Screen position and radius of each weak point still standing, with its index
+    pub fn targets(&self, width: usize, height: usize) -> Vec<(usize, f32, f32, f32)> {
+        let radius: f32 = self.weak_point_radius(width);
+        self.weak_points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| point.health > 0)
+            .map(|(i, point)| {
+                let (x, y) = self.weak_point_position(point, width, height);
+                (i, x, y, radius)
+            })
+            .collect()
+    }
+
+    // True the first time a burning beam crosses (x, y) on a width x height screen
+    pub fn burns(&mut self, x: f32, y: f32, width: usize, height: usize) -> bool {
+        let (ox, oy) = self.project(width, height);
+        let mut burned: bool = false;
+        for beam in self.beams.iter_mut() {
+            if !beam.is_burning() || beam.burned {
+                continue;
+            }
+            let (dx, dy) = beam.direction();
+            let along: f32 = (x - ox) * dx + (y - oy) * dy;
+            let across: f32 = ((x - ox) * dy - (y - oy) * dx).abs();
+            if along > 0.0 && across <= BEAM_WIDTH {
+                beam.burned = true;
+                burned = true;
+            }
+        }
+        burned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // A boss that has finished coming in
+    fn arrived(kind: BossKind) -> Boss {
+        let mut boss = Boss::new(kind);
+        let mut rng = SimpleRng::new(1);
+        while boss.is_arriving() {
+            assert!(boss.update(DT, &mut rng).is_empty());
+        }
+        boss
+    }
+
+    #[test]
+    fn test_shrugs_off_hits_while_arriving() {
+        let mut boss = Boss::new(BossKind::Warden);
+        assert_eq!(boss.damage(0, 3), Damage::Dented);
+        assert_eq!(boss.health_fraction(), 1.0);
+    }
+
+    #[test]
+    fn test_phases_follow_health() {
+        let mut boss = arrived(BossKind::Warden);
+        assert_eq!(boss.health(), 32);
+
+        let mut results: Vec<Damage> = Vec::new();
+        for point in 0..4 {
+            for _ in 0..8 {
+                // Wait out the shield after each phase change
+                boss.shielded = 0.0;
+                results.push(boss.damage(point, 1));
+            }
+        }
+        let count = |damage: Damage| results.iter().filter(|d| **d == damage).count();
+        assert_eq!(count(Damage::NextPhase), 2);
+        assert_eq!(count(Damage::PointDestroyed), 3);
+        assert_eq!(results.last(), Some(&Damage::Defeated));
+        assert_eq!(boss.phase, 2);
+        assert!(boss.targets(800, 600).is_empty());
+    }
+
+    #[test]
+    fn test_shielded_after_a_phase_change() {
+        let mut boss = arrived(BossKind::Hive);
+        for _ in 0..12 {
+            boss.damage(0, 1);
+            boss.damage(1, 1);
+        }
+        assert_eq!(boss.phase, 1);
+        let health = boss.health();
+        assert_eq!(boss.damage(2, 1), Damage::Dented);
+        assert_eq!(boss.health(), health);
+    }
+
+    #[test]
+    fn test_attacks_with_stars_then_beams() {
+        let mut boss = arrived(BossKind::Warden);
+        let mut rng = SimpleRng::new(2);
+        let mut stars: usize = 0;
+        for _ in 0..(4.0 / DT) as usize {
+            stars += boss.update(DT, &mut rng).len();
+        }
+        assert_eq!(stars, 5);
+        assert!(boss.beams.is_empty());
+
+        // Into the second phase: the next attack but one is a beam
+        boss.phase = 1;
+        for _ in 0..(7.0 / DT) as usize {
+            boss.update(DT, &mut rng);
+            if !boss.beams.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(boss.beams.len(), 1);
+        assert!(!boss.beams[0].is_burning());
+    }
+
+    #[test]
+    fn test_beam_burns_once() {
+        let mut boss = arrived(BossKind::Warden);
+        boss.beams.push(Beam::new(true));
+        let (ox, oy) = boss.project(800, 600);
+        // Straight below the boss, in the middle of the sweep
+        let (x, y) = (ox, oy + 200.0);
+        assert!(!boss.burns(x, y, 800, 600));
+
+        let mut rng = SimpleRng::new(3);
+        let mut burns: usize = 0;
+        for _ in 0..(3.0 / DT) as usize {
+            boss.update(DT, &mut rng);
+            let (ox, oy) = boss.project(800, 600);
+            if boss.burns(ox, oy + 200.0, 800, 600) {
+                burns += 1;
+            }
+        }
+        assert_eq!(burns, 1);
+    }
+}
@@ -0,0 +1,276 @@
+// A u32 (0RGB) framebuffer. Everything drawn through it is clipped to its bounds,
+// so callers can pass coordinates that are partly or fully off screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Canvas {
+    pub buffer: Vec<u32>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            buffer: vec![0u32; width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn clear(&mut self, color: u32) {
+        self.buffer.fill(color);
+    }
+
+    fn index(&self, x: isize, y: isize) -> Option<usize> {
+        if x >= 0 && x < self.width as isize && y >= 0 && y < self.height as isize {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get_pixel(&self, x: isize, y: isize) -> Option<u32> {
+        self.index(x, y).map(|idx| self.buffer[idx])
+    }
+
+    pub fn put_pixel(&mut self, x: isize, y: isize, color: u32) {
+        if let Some(idx) = self.index(x, y) {
+            self.buffer[idx] = color;
+        }
+    }
+
+    // Mix `color` over what is already there; alpha 1.0 replaces, 0.0 leaves it untouched
+    pub fn blend_pixel(&mut self, x: isize, y: isize, color: u32, alpha: f32) {
+        if let Some(idx) = self.index(x, y) {
+            let alpha: f32 = alpha.clamp(0.0, 1.0);
+            let dst: u32 = self.buffer[idx];
+            let mix = |shift: u32| -> u32 {
+                let s: f32 = ((color >> shift) & 0xFF) as f32;
+                let d: f32 = ((dst >> shift) & 0xFF) as f32;
+                ((s * alpha + d * (1.0 - alpha)) as u32).min(255) << shift
+            };
+            self.buffer[idx] = mix(16) | mix(8) | mix(0);
+        }
+    }
+
+    // Bresenham line, both endpoints included
+    pub fn line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize, color: u32) {
+        let dx: isize = (x1 - x0).abs();
+        let dy: isize = -(y1 - y0).abs();
+        let step_x: isize = if x0 < x1 { 1 } else { -1 };
+        let step_y: isize = if y0 < y1 { 1 } else { -1 };
+        let mut err: isize = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            self.put_pixel(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2: isize = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += step_x;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // Outline of a w x h rectangle with its top-left corner at (x, y)
+    pub fn rect(&mut self, x: isize, y: isize, w: usize, h: usize, color: u32) {
+        if w == 0 || h == 0 {
+            return;
+        }
+        let right: isize = x + w as isize - 1;
+        let bottom: isize = y + h as isize - 1;
+        self.line(x, y, right, y, color);
+        self.line(x, bottom, right, bottom, color);
+        self.line(x, y, x, bottom, color);
+        self.line(right, y, right, bottom, color);
+    }
+
+    pub fn fill_rect(&mut self, x: isize, y: isize, w: usize, h: usize, color: u32) {
+        let x_start: isize = x.max(0);
+        let y_start: isize = y.max(0);
+        let x_end: isize = (x + w as isize).min(self.width as isize);
+        let y_end: isize = (y + h as isize).min(self.height as isize);
+
+        for py in y_start..y_end {
+            let row: usize = py as usize * self.width;
+            for px in x_start..x_end {
+                self.buffer[row + px as usize] = color;
+            }
+        }
+    }
+
+    // Midpoint circle outline
+    pub fn circle(&mut self, cx: isize, cy: isize, radius: isize, color: u32) {
+        let mut x: isize = radius;
+        let mut y: isize = 0;
+        let mut err: isize = 1 - radius;
+
+        while x >= y {
+            for (px, py) in [
+                (x, y),
+                (y, x),
+                (-y, x),
+                (-x, y),
+                (-x, -y),
+                (-y, -x),
+                (y, -x),
+                (x, -y),
+            ] {
+                self.put_pixel(cx + px, cy + py, color);
+            }
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    pub fn filled_circle(&mut self, cx: isize, cy: isize, radius: isize, color: u32) {
+        self.filled_circle_with(cx, cy, radius, |_, _| color);
+    }
+
+    // Filled disc where the color is picked per pixel (dx, dy relative to the centre)
+    pub fn filled_circle_with<F>(&mut self, cx: isize, cy: isize, radius: isize, mut color: F)
+    where
+        F: FnMut(isize, isize) -> u32,
+    {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius {
+                    let c: u32 = color(dx, dy);
+                    self.put_pixel(cx + dx, cy + dy, c);
+                }
+            }
+        }
+    }
+
+    // Copy another canvas with its top-left corner at (x, y)
+    #[cfg(test)]
+    pub fn blit(&mut self, src: &Canvas, x: isize, y: isize) {
+        for sy in 0..src.height {
+            for sx in 0..src.width {
+                self.put_pixel(
+                    x + sx as isize,
+                    y + sy as isize,
+                    src.buffer[sy * src.width + sx],
+                );
+            }
+        }
+    }
+
+    // Copy `src` enlarged by a whole factor, each pixel becoming a scale x scale block,
+    // with its top-left corner at (0, 0)
+    pub fn blit_scaled(&mut self, src: &Canvas, scale: usize) {
+        for y in 0..self.height.min(src.height * scale) {
+            let src_row: &[u32] = &src.buffer[(y / scale) * src.width..][..src.width];
+            let dst_row: &mut [u32] = &mut self.buffer[y * self.width..][..self.width];
+            for (x, pixel) in dst_row.iter_mut().enumerate().take(src.width * scale) {
+                *pixel = src_row[x / scale];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(canvas: &Canvas) -> usize {
+        canvas.buffer.iter().filter(|p| **p != 0).count()
+    }
+
+    #[test]
+    fn test_put_pixel_clips() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.put_pixel(-1, 0, 1);
+        canvas.put_pixel(4, 0, 1);
+        canvas.put_pixel(0, 3, 1);
+        assert_eq!(lit(&canvas), 0);
+
+        canvas.put_pixel(3, 2, 0xABCDEF);
+        assert_eq!(canvas.get_pixel(3, 2), Some(0xABCDEF));
+        assert_eq!(canvas.get_pixel(4, 2), None);
+    }
+
+    #[test]
+    fn test_blend_pixel() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.put_pixel(0, 0, 0x0000FF);
+        canvas.blend_pixel(0, 0, 0xFF0000, 0.5);
+        assert_eq!(canvas.get_pixel(0, 0), Some(0x7F007F));
+
+        canvas.blend_pixel(1, 0, 0xFFAA00, 1.0);
+        assert_eq!(canvas.get_pixel(1, 0), Some(0xFFAA00));
+    }
+
+    #[test]
+    fn test_line_includes_endpoints() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.line(1, 1, 8, 5, 7);
+        assert_eq!(canvas.get_pixel(1, 1), Some(7));
+        assert_eq!(canvas.get_pixel(8, 5), Some(7));
+        assert_eq!(lit(&canvas), 8);
+    }
+
+    #[test]
+    fn test_rect_outline_and_fill() {
+        let mut canvas = Canvas::new(20, 20);
+        canvas.rect(2, 2, 9, 9, 1);
+        assert_eq!(lit(&canvas), 32);
+        assert_eq!(canvas.get_pixel(6, 6), Some(0));
+
+        canvas.clear(0);
+        canvas.fill_rect(-5, -5, 10, 10, 1);
+        assert_eq!(lit(&canvas), 25);
+    }
+
+    #[test]
+    fn test_circles_are_symmetric() {
+        let mut canvas = Canvas::new(21, 21);
+        canvas.filled_circle(10, 10, 6, 1);
+        for y in 0..21 {
+            for x in 0..21 {
+                assert_eq!(canvas.get_pixel(x, y), canvas.get_pixel(20 - x, y));
+                assert_eq!(canvas.get_pixel(x, y), canvas.get_pixel(x, 20 - y));
+            }
+        }
+
+        let mut outline = Canvas::new(21, 21);
+        outline.circle(10, 10, 6, 1);
+        assert_eq!(outline.get_pixel(16, 10), Some(1));
+        assert_eq!(outline.get_pixel(10, 10), Some(0));
+    }
+
+    #[test]
+    fn test_blit_clips() {
+        let mut sprite = Canvas::new(3, 3);
+        sprite.clear(5);
+        let mut canvas = Canvas::new(4, 4);
+        canvas.blit(&sprite, 2, -1);
+        assert_eq!(lit(&canvas), 4);
+        assert_eq!(canvas.get_pixel(3, 1), Some(5));
+    }
+
+    #[test]
+    fn test_blit_scaled() {
+        let mut small = Canvas::new(2, 2);
+        small.put_pixel(1, 0, 7);
+        let mut canvas = Canvas::new(7, 5);
+        canvas.blit_scaled(&small, 3);
+        assert_eq!(lit(&canvas), 9);
+        assert_eq!(canvas.get_pixel(3, 0), Some(7));
+        assert_eq!(canvas.get_pixel(5, 2), Some(7));
+        assert_eq!(canvas.get_pixel(6, 0), Some(0));
+        assert_eq!(canvas.get_pixel(2, 2), Some(0));
+    }
+}
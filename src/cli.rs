@@ -0,0 +1,198 @@
+use crate::display::DisplayMode;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: eagle [--seed <number>] [--tick-rate <hz>] [--record <file>] [--replay <file> [--headless]] [--dump-frames <dir>] [--fullscreen | --borderless] [--settings <file>] [--set <section.key>=<value>]...";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CliOptions {
+    pub seed: Option<u64>,
+    pub tick_rate: Option<u32>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    // Write every rendered frame as a numbered PNG into this directory
+    pub dump_frames: Option<PathBuf>,
+    // Settings file to use instead of the one in the config directory
+    pub settings: Option<PathBuf>,
+    // "section.key" and value pairs applied on top of the settings file, in order
+    pub overrides: Vec<(String, String)>,
+    // How the window starts out; F11 toggles fullscreen from there
+    pub display: DisplayMode,
+    pub help: bool,
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliOptions, String> {
+    let mut options: CliOptions = CliOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value: String = next_value(&mut args, "--seed")?;
+                let seed: u64 = value
+                    .parse()
+                    .map_err(|_| format!("--seed expects a number, got '{value}'"))?;
+                options.seed = Some(seed);
+            }
+            "--tick-rate" => {
+                let value: String = next_value(&mut args, "--tick-rate")?;
+                let tick_rate: u32 = value
+                    .parse()
+                    .ok()
+                    .filter(|hz| (10..=1000).contains(hz))
+                    .ok_or(format!("--tick-rate expects 10 to 1000, got '{value}'"))?;
+                options.tick_rate = Some(tick_rate);
+            }
+            "--record" => {
+                options.record = Some(PathBuf::from(next_value(&mut args, "--record")?));
+            }
+            "--replay" => {
+                options.replay = Some(PathBuf::from(next_value(&mut args, "--replay")?));
+            }
+            "--headless" => options.headless = true,
+            "--dump-frames" => {
+                options.dump_frames = Some(PathBuf::from(next_value(&mut args, "--dump-frames")?));
+            }
+            "--settings" => {
+                options.settings = Some(PathBuf::from(next_value(&mut args, "--settings")?));
+            }
+            "--set" => {
+                let value: String = next_value(&mut args, "--set")?;
+                let (key, value) = value
+                    .split_once('=')
+                    .ok_or(format!("--set expects section.key=value, got '{value}'"))?;
+                options
+                    .overrides
+                    .push((key.trim().to_string(), value.trim().to_string()));
+            }
+            "--fullscreen" | "--borderless" => {
+                if options.display != DisplayMode::Windowed {
+                    return Err("--fullscreen and --borderless can't be combined".to_string());
+                }
+                options.display = if arg == "--fullscreen" {
+                    DisplayMode::Fullscreen
+                } else {
+                    DisplayMode::Borderless
+                };
+            }
+            "-h" | "--help" => options.help = true,
+            other => return Err(format!("Unknown argument '{other}'")),
+        }
+    }
+
+    if options.headless && options.replay.is_none() {
+        return Err("--headless only works together with --replay".to_string());
+    }
+    let overrides_replay: bool = options.seed.is_some()
+        || options.tick_rate.is_some()
+        || options.record.is_some()
+        || options.settings.is_some()
+        || !options.overrides.is_empty();
+    if options.replay.is_some() && overrides_replay {
+        return Err(
+            "--replay takes its seed, tick rate and settings from the file and can't be combined with --seed, --tick-rate, --record, --settings or --set"
+                .to_string(),
+        );
+    }
+
+    Ok(options)
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{flag} needs a value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_no_args() {
+        assert_eq!(parse(&[]), Ok(CliOptions::default()));
+    }
+
+    #[test]
+    fn test_seed() {
+        assert_eq!(parse(&["--seed", "1234"]).unwrap().seed, Some(1234));
+    }
+
+    #[test]
+    fn test_seed_errors() {
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--seed", "abc"]).is_err());
+        assert!(parse(&["--seed", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_tick_rate() {
+        assert_eq!(parse(&["--tick-rate", "120"]).unwrap().tick_rate, Some(120));
+        assert!(parse(&["--tick-rate", "0"]).is_err());
+        assert!(parse(&["--tick-rate", "fast"]).is_err());
+        assert!(parse(&["--replay", "a.replay", "--tick-rate", "30"]).is_err());
+    }
+
+    #[test]
+    fn test_replay_options() {
+        let options = parse(&["--replay", "bug.replay", "--headless"]).unwrap();
+        assert_eq!(options.replay, Some(PathBuf::from("bug.replay")));
+        assert!(options.headless);
+
+        assert!(parse(&["--headless"]).is_err());
+        assert!(parse(&["--replay", "a.replay", "--seed", "1"]).is_err());
+        assert!(parse(&["--replay", "a.replay", "--record", "b.replay"]).is_err());
+    }
+
+    #[test]
+    fn test_dump_frames() {
+        let options =
+            parse(&["--replay", "a.replay", "--headless", "--dump-frames", "out"]).unwrap();
+        assert_eq!(options.dump_frames, Some(PathBuf::from("out")));
+        assert!(parse(&["--dump-frames"]).is_err());
+    }
+
+    #[test]
+    fn test_settings_overrides() {
+        let options = parse(&[
+            "--settings",
+            "hard.toml",
+            "--set",
+            "difficulty.max_escaped=3",
+            "--set",
+            "window.width = 1024",
+        ])
+        .unwrap();
+        assert_eq!(options.settings, Some(PathBuf::from("hard.toml")));
+        assert_eq!(
+            options.overrides,
+            vec![
+                ("difficulty.max_escaped".to_string(), "3".to_string()),
+                ("window.width".to_string(), "1024".to_string()),
+            ]
+        );
+        assert!(parse(&["--set", "max_escaped"]).is_err());
+        assert!(parse(&["--replay", "a.replay", "--set", "window.width=1024"]).is_err());
+    }
+
+    #[test]
+    fn test_display_modes() {
+        assert_eq!(parse(&[]).unwrap().display, DisplayMode::Windowed);
+        assert_eq!(
+            parse(&["--fullscreen"]).unwrap().display,
+            DisplayMode::Fullscreen
+        );
+        assert_eq!(
+            parse(&["--borderless"]).unwrap().display,
+            DisplayMode::Borderless
+        );
+        assert!(parse(&["--borderless", "--fullscreen"]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_argument() {
+        assert!(parse(&["--speed", "1"]).is_err());
+    }
+}
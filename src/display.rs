@@ -0,0 +1,91 @@
+use minifb::{Scale, ScaleMode, Window, WindowOptions};
+
+const TITLE: &str = "Starfield + Ship + Particles - ESC to exit";
+
+// How the game is presented. Windowed draws at whatever size the window is; the other two keep
+// the logical play-field framebuffer and let minifb scale it up, letterboxed to keep its shape.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    // minifb can't take over the display, so this is a borderless window on top of everything,
+    // grown as large as the screen allows
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn window_options(self) -> WindowOptions {
+        match self {
+            DisplayMode::Windowed => WindowOptions {
+                resize: true,
+                ..WindowOptions::default()
+            },
+            DisplayMode::Borderless => WindowOptions {
+                borderless: true,
+                title: false,
+                scale_mode: ScaleMode::AspectRatioStretch,
+                ..WindowOptions::default()
+            },
+            DisplayMode::Fullscreen => WindowOptions {
+                borderless: true,
+                title: false,
+                topmost: true,
+                scale: Scale::FitScreen,
+                scale_mode: ScaleMode::AspectRatioStretch,
+                ..WindowOptions::default()
+            },
+        }
+    }
+
+    // Whether the canvas should follow the window size rather than stay at the play-field size
+    pub fn follows_window(self) -> bool {
+        self == DisplayMode::Windowed
+    }
+
+    // The fullscreen toggle: into fullscreen, and back out to whatever the game was launched with
+    pub fn toggled(self, launched: DisplayMode) -> DisplayMode {
+        match (self, launched) {
+            (DisplayMode::Fullscreen, DisplayMode::Fullscreen) => DisplayMode::Windowed,
+            (DisplayMode::Fullscreen, launched) => launched,
+            _ => DisplayMode::Fullscreen,
+        }
+    }
+}
+
+pub fn open_window(mode: DisplayMode, width: usize, height: usize) -> Result<Window, String> {
+    let mut window: Window = Window::new(TITLE, width, height, mode.window_options())
+        .map_err(|e| format!("Could not open a {width}x{height} window: {e}"))?;
+    // Caps the render rate; the simulation runs at its own tick rate
+    window.set_target_fps(60);
+    Ok(window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaled_modes_letterbox() {
+        for mode in [DisplayMode::Borderless, DisplayMode::Fullscreen] {
+            let options = mode.window_options();
+            assert!(options.borderless && !options.resize);
+            assert_eq!(options.scale_mode, ScaleMode::AspectRatioStretch);
+            assert!(!mode.follows_window());
+        }
+        assert!(DisplayMode::Windowed.window_options().resize);
+        assert!(DisplayMode::Windowed.follows_window());
+    }
+
+    #[test]
+    fn test_toggle_returns_to_launch_mode() {
+        use DisplayMode::*;
+        assert_eq!(Windowed.toggled(Windowed), Fullscreen);
+        assert_eq!(Fullscreen.toggled(Windowed), Windowed);
+        assert_eq!(Borderless.toggled(Borderless), Fullscreen);
+        assert_eq!(Fullscreen.toggled(Borderless), Borderless);
+        // Launched fullscreen, the only way out is a normal window
+        assert_eq!(Fullscreen.toggled(Fullscreen), Windowed);
+        assert_eq!(Windowed.toggled(Fullscreen), Fullscreen);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::canvas::Canvas;
 use std::collections::HashMap;
 
 pub const FONT: [[u8; 5]; 10] = [
@@ -162,37 +163,13 @@ pub fn get_symbols() -> HashMap<char, [u8; 7]> {
     symbols
 }
 
-fn draw_digit(
-    buffer: &mut [u32],
-    width: usize,
-    x: usize,
-    y: usize,
-    digit: usize,
-    color: u32,
-    scale: usize,
-) {
+fn draw_digit(canvas: &mut Canvas, x: usize, y: usize, digit: usize, color: u32, scale: usize) {
     let font: [u8; 5] = FONT[digit];
-    for (dy, row) in font.iter().enumerate() {
-        for dx in 0..5 {
-            if (row >> (4 - dx)) & 1 == 1 {
-                // Draw a scale x scale block
-                for sy in 0..scale {
-                    for sx in 0..scale {
-                        let px: usize = x + dx * scale + sx;
-                        let py: usize = y + dy * scale + sy;
-                        if px < width && py < buffer.len() / width {
-                            buffer[py * width + px] = color;
-                        }
-                    }
-                }
-            }
-        }
-    }
+    draw_glyph(canvas, x, y, &font, color, scale);
 }
 
 pub fn draw_number(
-    buffer: &mut [u32],
-    width: usize,
+    canvas: &mut Canvas,
     x: usize,
     y: usize,
     number: usize,
@@ -203,8 +180,7 @@ pub fn draw_number(
     for (i, d) in digits.iter().enumerate() {
         if let Some(digit) = d.to_digit(10) {
             draw_digit(
-                buffer,
-                width,
+                canvas,
                 x + i * (5 * scale + scale),
                 y,
                 digit as usize,
@@ -215,47 +191,17 @@ pub fn draw_number(
     }
 }
 
-fn draw_letter(
-    buffer: &mut [u32],
-    width: usize,
-    x: usize,
-    y: usize,
-    ch: char,
-    color: u32,
-    scale: usize,
-) {
+fn draw_letter(canvas: &mut Canvas, x: usize, y: usize, ch: char, color: u32, scale: usize) {
     if ch.is_ascii_alphabetic() {
         let index: usize = (ch.to_ascii_uppercase() as u8 - b'A') as usize;
         if index < 26 {
             let font: [u8; 7] = LETTERS[index];
-            for (dy, row) in font.iter().enumerate() {
-                for dx in 0..5 {
-                    if (row >> (4 - dx)) & 1 == 1 {
-                        for sy in 0..scale {
-                            for sx in 0..scale {
-                                let px: usize = x + dx * scale + sx;
-                                let py: usize = y + dy * scale + sy;
-                                if px < width && py < buffer.len() / width {
-                                    buffer[py * width + px] = color;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            draw_glyph(canvas, x, y, &font, color, scale);
         }
     }
 }
 
-pub fn draw_text(
-    buffer: &mut [u32],
-    width: usize,
-    x: usize,
-    y: usize,
-    text: &str,
-    color: u32,
-    scale: usize,
-) {
+pub fn draw_text(canvas: &mut Canvas, x: usize, y: usize, text: &str, color: u32, scale: usize) {
     let symbols: HashMap<char, [u8; 7]> = get_symbols();
 
     for (i, ch) in text.chars().enumerate() {
@@ -263,8 +209,7 @@ pub fn draw_text(
 
         if ch.is_ascii_digit() {
             draw_digit(
-                buffer,
-                width,
+                canvas,
                 px,
                 y,
                 ch.to_digit(10).unwrap() as usize,
@@ -272,34 +217,59 @@ pub fn draw_text(
                 scale,
             );
         } else if ch.is_ascii_alphabetic() {
-            draw_letter(buffer, width, px, y, ch, color, scale);
+            draw_letter(canvas, px, y, ch, color, scale);
         } else if let Some(pattern) = symbols.get(&ch) {
-            draw_custom_glyph(buffer, width, px, y, pattern, color, scale);
+            draw_glyph(canvas, px, y, pattern, color, scale);
         }
     }
 }
 
-fn draw_custom_glyph(
-    buffer: &mut [u32],
+// Width in pixels of `text` drawn by draw_text, without the trailing gap
+pub fn text_width(text: &str, scale: usize) -> usize {
+    (text.chars().count() * 6 * scale).saturating_sub(scale)
+}
+
+// Corner of the screen a HUD element hangs from, so it stays put when the window is resized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+// Top-left corner for a width x height block placed (dx, dy) in from the anchor's corner
+pub fn anchored(
+    canvas: &Canvas,
+    anchor: Anchor,
+    dx: usize,
+    dy: usize,
     width: usize,
-    x: usize,
-    y: usize,
-    pattern: &[u8; 7],
-    color: u32,
-    scale: usize,
-) {
-    for (dy, row) in pattern.iter().enumerate() {
+    height: usize,
+) -> (usize, usize) {
+    match anchor {
+        Anchor::TopLeft => (dx, dy),
+        Anchor::TopRight => (canvas.width.saturating_sub(dx + width), dy),
+        Anchor::BottomLeft => (dx, canvas.height.saturating_sub(dy + height)),
+        Anchor::BottomRight => (
+            canvas.width.saturating_sub(dx + width),
+            canvas.height.saturating_sub(dy + height),
+        ),
+    }
+}
+
+// Each row is 5 bits wide, most significant bit on the left; every set bit becomes a scale x scale block
+fn draw_glyph(canvas: &mut Canvas, x: usize, y: usize, rows: &[u8], color: u32, scale: usize) {
+    for (dy, row) in rows.iter().enumerate() {
         for dx in 0..5 {
             if (row >> (4 - dx)) & 1 == 1 {
-                for sy in 0..scale {
-                    for sx in 0..scale {
-                        let px: usize = x + dx * scale + sx;
-                        let py: usize = y + dy * scale + sy;
-                        if px < width && py < buffer.len() / width {
-                            buffer[py * width + px] = color;
-                        }
-                    }
-                }
+                canvas.fill_rect(
+                    (x + dx * scale) as isize,
+                    (y + dy * scale) as isize,
+                    scale,
+                    scale,
+                    color,
+                );
             }
         }
     }
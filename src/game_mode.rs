@@ -0,0 +1,174 @@
+use crate::highscores::civil_from_days;
+
+// Seconds on the clock in time attack
+pub const TIME_ATTACK_SECONDS: f64 = 120.0;
+
+// The rules a game is played by, picked on the title screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    // The levels, then stars until too many get away or the ships run out
+    #[default]
+    Endless,
+    // As many points as possible before the clock runs out, in a thicker stream
+    TimeAttack,
+    // Nothing to lose: no damage, no misses, no ending
+    Zen,
+    // Endless on a seed everyone gets on the same day, with its own table
+    Daily,
+}
+
+pub const GAME_MODES: [GameMode; 4] = [
+    GameMode::Endless,
+    GameMode::TimeAttack,
+    GameMode::Zen,
+    GameMode::Daily,
+];
+
+impl GameMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Endless => "ENDLESS",
+            GameMode::TimeAttack => "TIME ATTACK",
+            GameMode::Zen => "ZEN",
+            GameMode::Daily => "DAILY CHALLENGE",
+        }
+    }
+
+    // One line for the title screen
+    pub fn description(self) -> &'static str {
+        match self {
+            GameMode::Endless => "PLAY THE LEVELS, THEN SURVIVE AS LONG AS YOU CAN",
+            GameMode::TimeAttack => "SCORE ALL YOU CAN IN 120 SECONDS",
+            GameMode::Zen => "NO DAMAGE, NO MISSES, NO HURRY",
+            GameMode::Daily => "THE SAME GAME FOR EVERYONE TODAY",
+        }
+    }
+
+    // The name used in the high-score file
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Endless => "endless",
+            GameMode::TimeAttack => "time-attack",
+            GameMode::Zen => "zen",
+            GameMode::Daily => "daily",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GAME_MODES.into_iter().find(|mode| mode.name() == name)
+    }
+
+    // The byte stored in replays; fixed per mode, whatever order GAME_MODES is in
+    pub fn id(self) -> u8 {
+        match self {
+            GameMode::Endless => 0,
+            GameMode::TimeAttack => 1,
+            GameMode::Zen => 2,
+            GameMode::Daily => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<GameMode> {
+        GAME_MODES.into_iter().find(|mode| mode.id() == id)
+    }
+
+    // The next mode along, or the one before when `back`, wrapping round
+    pub fn cycle(self, back: bool) -> GameMode {
+        let index: usize = GAME_MODES
+            .iter()
+            .position(|mode| *mode == self)
+            .unwrap_or(0);
+        let step: usize = if back { GAME_MODES.len() - 1 } else { 1 };
+        GAME_MODES[(index + step) % GAME_MODES.len()]
+    }
+
+    // Whether the game can end at all
+    pub fn can_fail(self) -> bool {
+        self != GameMode::Zen
+    }
+
+    // Whether too many missed stars end the game; in time attack they only cost points not won
+    pub fn misses_end_game(self) -> bool {
+        matches!(self, GameMode::Endless | GameMode::Daily)
+    }
+
+    pub fn time_limit(self) -> Option<f64> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_SECONDS),
+            _ => None,
+        }
+    }
+
+    // Whether it goes through the levels before the endless stream
+    pub fn plays_levels(self) -> bool {
+        matches!(self, GameMode::Endless | GameMode::Daily)
+    }
+
+    // Scales how many stars a second the endless stream starts with, and how fast that grows
+    pub fn spawn_scale(self) -> f64 {
+        match self {
+            GameMode::TimeAttack => 2.5,
+            _ => 1.0,
+        }
+    }
+
+    pub fn ramp_scale(self) -> f64 {
+        match self {
+            GameMode::Zen => 0.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn has_bosses(self) -> bool {
+        self != GameMode::Zen
+    }
+
+    // Whether it's played on the default difficulty whatever the settings say, so that everyone's
+    // scores compare
+    pub fn fixes_difficulty(self) -> bool {
+        self == GameMode::Daily
+    }
+
+    pub fn has_high_scores(self) -> bool {
+        self != GameMode::Zen
+    }
+}
+
+// The daily challenge's seed for a Unix time: the UTC date as YYYYMMDD, so it can be read off the
+// high-score table and typed back in with --seed
+pub fn daily_seed(unix_seconds: u64) -> u64 {
+    let (year, month, day) = civil_from_days((unix_seconds / 86_400) as i64);
+    year as u64 * 10_000 + month as u64 * 100 + day as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for mode in GAME_MODES {
+            assert_eq!(GameMode::from_name(mode.name()), Some(mode));
+        }
+        assert_eq!(GameMode::from_name("hard"), None);
+        for mode in GAME_MODES {
+            assert_eq!(GameMode::from_id(mode.id()), Some(mode));
+        }
+        assert_eq!(GameMode::from_id(4), None);
+    }
+
+    #[test]
+    fn test_cycle_wraps() {
+        assert_eq!(GameMode::Endless.cycle(false), GameMode::TimeAttack);
+        assert_eq!(GameMode::Endless.cycle(true), GameMode::Daily);
+        assert_eq!(GameMode::Daily.cycle(false), GameMode::Endless);
+    }
+
+    #[test]
+    fn test_daily_seed_is_the_date() {
+        // 2025-10-18, early and late in the day
+        assert_eq!(daily_seed(1_760_745_600), 20251018);
+        assert_eq!(daily_seed(1_760_745_600 + 86_399), 20251018);
+        assert_eq!(daily_seed(1_760_745_600 + 86_400), 20251019);
+    }
+}
@@ -1,141 +1,305 @@
+use crate::boss::{BEAM_WIDTH, Boss, Damage};
+use crate::canvas::Canvas;
+use crate::draw_text::{draw_text, text_width};
+use crate::game_mode::GameMode;
+use crate::health::{Health, Hurt};
+use crate::input::InputState;
+use crate::levels::{Campaign, Progress};
 use crate::particles::Particle;
-use crate::sound::{play_noise_boom, play_pitched_tone, saw_wave, square_wave};
-use crate::space_objects::{BigStar, Star};
-use crate::utils::{blend_color, distance_squared, generate_big_star_color};
-use minifb::{Key, Window};
+use crate::powerups::{Boosts, PowerUp, PowerUpKind};
+use crate::replay::{Replay, ReplayResult};
+use crate::scoring::{FloatingText, Scoring};
+use crate::settings::Settings;
 use crate::simple_random::SimpleRng;
+use crate::sound::{AudioSink, SoundEvent};
+use crate::space_objects::{BigStar, Hitbox, Ship, Star, StarKind};
+use crate::utils::{blend_color, distance_squared, generate_big_star_color};
+use crate::weapons::{Armory, Energy, FULL_CHARGE, Projectile, Weapon};
+
+pub const DEFAULT_TICK_RATE: u32 = 60;
+
+// Speeds are per second so they hold at any tick rate
+const STAR_SPEED: f32 = 0.6;
+const FAR_STAR_SPEED: f32 = 0.18;
+const NEAR_STAR_SPEED: f32 = 0.6;
+const PARTICLE_SPEED: f32 = 240.0;
+const SCREEN_SHAKE_TIME: f32 = 10.0 / 60.0;
+// Reach of the shield bubble and of the ship when collecting pickups, in pixels
+const SHIELD_RADIUS: f32 = 20.0;
+const PICKUP_RADIUS: f32 = 12.0;
+// Fraction of the way to the ship the magnet pulls a star each second
+const MAGNET_PULL: f32 = 1.5;
+// A star that gets this close to the ship while this near the camera, without hitting it, is a
+// near miss
+const NEAR_MISS_RADIUS: f32 = 24.0;
+const NEAR_MISS_DEPTH: f32 = 0.6;
+// Points for a boss's weak points and for beating it, in the same terms as a star's worth
+const WEAK_POINT_WORTH: f32 = 5.0;
+const BOSS_WORTH: f32 = 50.0;
+// Shots' worth of damage a bomb does to a boss's weak points in reach
+const BOMB_DAMAGE: u8 = 2;
+// Damage a boss's beam does to the ship
+const BEAM_DAMAGE: usize = 2;
 
 pub struct GameState {
-    pub ship_x: i32,
-    pub ship_y: i32,
+    pub settings: Settings,
+    // Decides what comes in, what ends the game and which table the score goes on
+    pub mode: GameMode,
+    pub ship: Ship,
 
     pub stars: Vec<Star>,
     pub big_stars: Vec<BigStar>,
+    pub boss: Option<Boss>,
     pub particles: Vec<Particle>,
-
-    pub screen_shake_timer: i32,
+    pub armory: Armory,
+    pub energy: Energy,
+    pub projectiles: Vec<Projectile>,
+    pub power_ups: Vec<PowerUp>,
+    pub boosts: Boosts,
+    pub health: Health,
+
+    pub screen_shake_timer: f32,
     pub shake_timer: f32,
     pub shake_duration: f32,
-    pub space_cooldown_timer: f32,
 
+    pub scoring: Scoring,
+    pub floating_texts: Vec<FloatingText>,
+    // Stars destroyed, however it happened
     pub collision_count: usize,
     pub missed_count: usize,
-    pub total_seconds: usize,
+    pub elapsed_seconds: f64,
     pub redemption_flash_timer: f32,
     pub near_stars: Vec<Star>,
     pub far_stars: Vec<Star>,
+    // Which level and wave the game is on, and what comes next
+    pub campaign: Campaign,
     pub big_star_spawned_count: u32,
+    // Extra stars that splitters broke into
+    pub big_star_shard_count: u32,
+    // Missed stars that left for good; in endless play they come back as new ones instead
+    pub big_star_escaped_count: u32,
+
+    pub seed: u64,
+    // Gameplay stream: spawning, movement, collision jitter. Replays depend on it.
+    pub rng: SimpleRng,
+    // Cosmetic stream: background stars, particles, colors
+    pub fx_rng: SimpleRng,
+
+    // Length of one simulation tick in seconds
+    pub dt: f32,
+
+    // Every frame's input, so the session can be saved and replayed
+    pub replay: Replay,
+    pub game_over_frame: Option<usize>,
 }
 
 impl GameState {
-    pub fn new(width: usize, height: usize, num_stars: usize) -> Self {
-        let mut rng = SimpleRng::new();
+    pub fn new(settings: &Settings, seed: u64, tick_rate: u32, mode: GameMode) -> Self {
+        let settings: Settings = if mode.fixes_difficulty() {
+            settings.with_default_difficulty()
+        } else {
+            settings.clone()
+        };
+        let num_stars: usize = settings.stars;
+        let mut rng: SimpleRng = SimpleRng::new(seed);
+        let mut fx_rng: SimpleRng = rng.fork();
         let stars: Vec<Star> = (0..num_stars)
             .map(|_| Star {
-                x: rng.random_range_f32(-1.0..1.0),
-                y: rng.random_range_f32(-1.0..1.0),
-                z: rng.random_range_f32(0.1..1.0),
+                x: fx_rng.random_range_f32(-1.0..1.0),
+                y: fx_rng.random_range_f32(-1.0..1.0),
+                z: fx_rng.random_range_f32(0.1..1.0),
             })
             .collect();
 
         let near_stars: Vec<Star> = (0..(num_stars / 2))
             .map(|_| Star {
-                x: rng.random_range_f32(-1.0..1.0),
-                y: rng.random_range_f32(-1.0..1.0),
-                z: rng.random_range_f32(0.1..1.0),
+                x: fx_rng.random_range_f32(-1.0..1.0),
+                y: fx_rng.random_range_f32(-1.0..1.0),
+                z: fx_rng.random_range_f32(0.1..1.0),
             })
             .collect();
 
         let far_stars: Vec<Star> = (0..(num_stars / 2))
             .map(|_| Star {
-                x: rng.random_range_f32(-1.0..1.0),
-                y: rng.random_range_f32(-1.0..1.0),
-                z: rng.random_range_f32(1.0..2.5),
+                x: fx_rng.random_range_f32(-1.0..1.0),
+                y: fx_rng.random_range_f32(-1.0..1.0),
+                z: fx_rng.random_range_f32(1.0..2.5),
             })
             .collect();
 
         Self {
-            ship_x: (width / 2) as i32,
-            ship_y: (height / 2) as i32,
+            settings: settings.clone(),
+            mode,
+            ship: Ship::new((settings.width / 2) as f32, (settings.height / 2) as f32),
             stars,
             big_stars: Vec::new(),
+            boss: None,
             particles: Vec::new(),
-            screen_shake_timer: 0,
+            armory: Armory::new(settings.bombs),
+            energy: Energy::default(),
+            projectiles: Vec::new(),
+            power_ups: Vec::new(),
+            boosts: Boosts::default(),
+            health: Health::new(&settings),
+            screen_shake_timer: 0.0,
             shake_timer: 0.0,
             shake_duration: 0.0,
-            space_cooldown_timer: 0.0,
+            scoring: Scoring::default(),
+            floating_texts: Vec::new(),
             collision_count: 0,
             missed_count: 0,
-            total_seconds: 0,
+            elapsed_seconds: 0.0,
             redemption_flash_timer: 0.0,
-            near_stars: near_stars,
-            far_stars: far_stars,
+            near_stars,
+            far_stars,
+            campaign: Campaign::for_mode(mode, settings.start_level - 1),
             big_star_spawned_count: 0,
+            big_star_shard_count: 0,
+            big_star_escaped_count: 0,
+            seed,
+            rng,
+            fx_rng,
+            dt: 1.0 / tick_rate as f32,
+            replay: Replay::new(seed, tick_rate, settings, mode),
+            game_over_frame: None,
+        }
+    }
+
+    // One simulation tick of `dt` seconds. Only advances state; drawing is done by `render`.
+    pub fn step(&mut self, input: &InputState, audio: &mut dyn AudioSink) {
+        self.replay.record(input);
+        self.ship.prev_x = self.ship.x;
+        self.ship.prev_y = self.ship.y;
+
+        self.update(audio);
+        self.check_and_shake(audio);
+
+        // Remove hit big stars
+        self.big_stars.retain(|star| !star.hit);
+
+        self.handle_input(input, audio);
+
+        if self.game_over_frame.is_none() && self.is_game_over() {
+            self.game_over_frame = Some(self.replay.frames.len());
+        }
+    }
+
+    // Out of ships or out of time, or too many stars got away where the mode counts that
+    pub fn is_game_over(&self) -> bool {
+        if !self.mode.can_fail() {
+            return false;
         }
+        let too_many_missed: bool =
+            self.mode.misses_end_game() && self.missed_count >= self.settings.max_escaped;
+        self.health.lives == 0 || too_many_missed || self.time_left() == Some(0.0)
     }
 
-    pub fn update(
-        &mut self,
-        _window: &Window,
-        buffer: &mut [u32],
-        width: usize,
-        height: usize,
-        stream_handle: &rodio::OutputStreamHandle,
-    ) {
-        // Clear screen
-        buffer.fill(0);
+    // Seconds to go, in a mode that's against the clock
+    pub fn time_left(&self) -> Option<f64> {
+        self.mode
+            .time_limit()
+            .map(|limit| (limit - self.elapsed_seconds).max(0.0))
+    }
 
-        // Cooldown timer (e.g. for spacebar)
-        if self.space_cooldown_timer > 0.0 {
-            self.space_cooldown_timer -= 1.0 / 60.0; // assume 60 FPS
+    pub fn result(&self) -> ReplayResult {
+        ReplayResult {
+            collision_count: self.collision_count,
+            score: self.scoring.score,
+            missed_count: self.missed_count,
+            game_over_frame: self.game_over_frame,
         }
+    }
+
+    // Snapshot of the recording with the outcome attached, ready to be saved
+    pub fn finished_replay(&self) -> Replay {
+        let mut replay: Replay = self.replay.clone();
+        replay.result = Some(self.result());
+        replay
+    }
 
-        self.update_starfield(buffer, width, height); // (if you’ve made this)
-        let missed: usize = self.update_big_stars(buffer, width, height);
+    pub fn update(&mut self, audio: &mut dyn AudioSink) {
+        let dt: f32 = self.dt;
+
+        self.armory.tick(dt);
+        self.energy.regen(dt);
+        self.boosts.tick(dt);
+        self.health.tick(dt);
+        self.scoring.tick(dt);
+        self.floating_texts
+            .retain_mut(|text: &mut FloatingText| text.update(dt));
+        if self.shake_timer > 0.0 {
+            self.shake_timer -= dt;
+        }
+
+        self.update_starfield();
+        self.spawn_big_stars(audio);
+        let missed: usize = self.update_big_stars();
+        self.update_boss(audio);
         if missed > 0 {
             self.missed_count += missed;
-            crate::sound::play_pitched_tone(50.0, 0.25, crate::sound::square_wave, stream_handle);
+            audio.play(SoundEvent::Missed);
             println!(
                 "Missed {} star(s)! Total missed: {}",
                 missed, self.missed_count
             );
         }
 
-        self.update_particles(buffer, width, height);
+        self.update_particles();
+        self.update_projectiles(audio);
+        self.update_power_ups(audio);
 
-        // Update and draw starfield
-        let mut rng = SimpleRng::new();
+        self.elapsed_seconds += dt as f64;
 
-        for star in self.stars.iter_mut() {
-            star.z -= 0.01;
+        if self.redemption_flash_timer > 0.0 {
+            self.redemption_flash_timer -= dt;
+        }
+    }
 
-            if star.z <= 0.01 {
-                star.x = rng.random_range_f32(-1.0..1.0);
-                star.y = rng.random_range_f32(-1.0..1.0);
-                star.z = 1.0;
-            }
+    // Draws the current state. Never mutates, so it can be called any number of times per tick
+    // (e.g. to redraw behind a pause screen). alpha is how far into the next tick we are.
+    pub fn render(&self, canvas: &mut Canvas, alpha: f32) {
+        canvas.clear(0);
 
-            // Project 3D -> 2D
-            let sx: isize = (star.x / star.z * width as f32 / 2.0 + width as f32 / 2.0) as isize;
-            let sy: isize = (star.y / star.z * height as f32 / 2.0 + height as f32 / 2.0) as isize;
+        // How far behind the latest tick this frame is, in seconds
+        let lag: f32 = (1.0 - alpha) * self.dt;
 
-            // Draw if on screen
-            if sx >= 0 && sx < width as isize && sy >= 0 && sy < height as isize {
-                let brightness: u32 = ((1.0 - star.z) * 255.0) as u32;
-                let color: u32 = (brightness << 16) | (brightness << 8) | brightness;
-                buffer[sy as usize * width + sx as usize] = color;
-            }
+        draw_stars(&self.far_stars, canvas, FAR_STAR_SPEED * lag);
+        draw_stars(&self.near_stars, canvas, NEAR_STAR_SPEED * lag);
+        draw_stars(&self.stars, canvas, STAR_SPEED * lag);
+
+        let view: View = View::new(&self.settings, canvas);
+        let (ship_x, ship_y) = self.ship.interpolated(alpha);
+        // The boss hangs back behind the stars it sends
+        if let Some(boss) = &self.boss {
+            render_boss(canvas, view, boss);
+        }
+        self.render_big_stars(canvas, view, ship_x, ship_y);
+        self.render_power_ups(canvas, view);
+        self.render_projectiles(canvas, view, lag);
+        self.render_particles(canvas, view, lag);
+        self.render_floating_texts(canvas, view);
+
+        let (shake_offset_x, shake_offset_y) = self.shake_offsets();
+        let (screen_x, screen_y) = view.point(ship_x + shake_offset_x, ship_y + shake_offset_y);
+        let draw_x: isize = screen_x.round() as isize;
+        let draw_y: isize = screen_y.round() as isize;
+
+        let pixel: usize = view.size.round().max(1.0) as usize;
+        if self.health.lives > 0 && self.health.is_visible() {
+            self.ship.draw(canvas, draw_x, draw_y, 0xFFFFFF, pixel);
         }
 
-        // Count seconds passed (assuming 60 FPS)
-        self.total_seconds += 1;
+        if self.boosts.is_active(PowerUpKind::Shield) {
+            let radius: isize = (SHIELD_RADIUS * view.size).round() as isize;
+            canvas.circle(draw_x, draw_y, radius, PowerUpKind::Shield.color());
+        }
 
         if self.redemption_flash_timer > 0.0 {
-            self.redemption_flash_timer -= 1.0 / 60.0;
+            self.flash(canvas, draw_x, draw_y, 0x00FF00, view.size);
         }
     }
 
-    pub fn shake_offsets(&mut self) -> (f32, f32) {
+    pub fn shake_offsets(&self) -> (f32, f32) {
         if self.shake_timer <= 0.0 {
             return (0.0, 0.0);
         }
@@ -144,35 +308,34 @@ impl GameState {
         let amplitude: f32 = 5.0 * (1.0 - progress);
         let frequency: f32 = 30.0;
 
-        self.shake_timer -= 1.0 / 60.0;
-
         let shake_x: f32 = (progress * frequency * std::f32::consts::TAU).sin() * amplitude;
         let shake_y: f32 = ((progress * frequency + 0.5) * std::f32::consts::TAU).sin() * amplitude;
 
         (shake_x, shake_y)
     }
 
-    pub fn check_collisions(
-        &mut self,
-        stream_handle: &rodio::OutputStreamHandle,
-        width: usize,
-        height: usize,
-        num_particles: usize,
-        offset_x: f32,
-        offset_y: f32,
-    ) {
-        let mut rng = SimpleRng::new();
-        let ship_x: f32 = self.ship_x as f32 + offset_x;
-        let ship_y: f32 = self.ship_y as f32 + offset_y;
+    pub fn check_collisions(&mut self, audio: &mut dyn AudioSink, offset_x: f32, offset_y: f32) {
+        let width: usize = self.settings.width;
+        let height: usize = self.settings.height;
+        let mut hitbox: Hitbox = self.ship.hitbox();
+        hitbox.x += offset_x;
+        hitbox.y += offset_y;
+
+        // The shield catches stars a little way out from the ship
+        let shield: f32 = if self.boosts.is_active(PowerUpKind::Shield) {
+            SHIELD_RADIUS
+        } else {
+            0.0
+        };
 
         // Step 1: Find indices of stars to explode
         let mut to_explode: Vec<usize> = vec![];
 
         for (i, star) in self.big_stars.iter().enumerate() {
-            let px: f32 = star.x / star.z * width as f32 / 2.0 + width as f32 / 2.0;
-            let py: f32 = star.y / star.z * height as f32 / 2.0 + height as f32 / 2.0;
+            let (px, py) = star.project(width, height);
+            let shielded: bool = distance_squared(px, py, hitbox.x, hitbox.y) < shield * shield;
 
-            if !star.hit && distance_squared(px, py, ship_x, ship_y) < 12.0 {
+            if !star.hit && (hitbox.contains(px, py) || shielded) {
                 to_explode.push(i);
             }
         }
@@ -181,12 +344,18 @@ impl GameState {
         for i in to_explode {
             let star: &mut BigStar = &mut self.big_stars[i];
             star.hit = true;
+            let star: BigStar = star.clone();
 
             self.reset_shake();
-            play_pitched_tone(110.0, 0.25, saw_wave, stream_handle);
-            self.collision_count += 1;
-
-            if self.missed_count > 0 {
+            audio.play(SoundEvent::Collision);
+            self.star_destroyed(&star, true);
+
+            // Harmless stars are a catch that takes back a miss; the rest hurt unless the
+            // shield boost is up, and are never a catch
+            let harmful: bool = star.kind.contact_damage() > 0;
+            if harmful && shield <= 0.0 {
+                self.hurt_ship(star.kind.contact_damage(), audio);
+            } else if !harmful && self.missed_count > 0 {
                 self.missed_count -= 1;
                 self.redemption_flash_timer = 0.3;
             }
@@ -195,60 +364,96 @@ impl GameState {
                 self.collision_count, self.missed_count
             );
 
-            for _ in 0..num_particles {
-                let life: u32 = rng.random_range_u32(50..100);
-                self.particles.push(crate::particles::Particle {
-                    x: ship_x,
-                    y: ship_y,
-                    vx: rng.random_range_f32(-4.0..4.0),
-                    vy: rng.random_range_f32(-4.0..4.0),
-                    life,
-                    initial_life: life,
-                });
-            }
+            self.spawn_explosion(hitbox.x, hitbox.y);
         }
 
         self.big_stars.retain(|s: &BigStar| !s.hit);
-    }
 
-    pub fn handle_input(
-        &mut self,
-        window: &Window,
-        stream_handle: &rodio::OutputStreamHandle,
-        width: usize,
-        height: usize,
-        num_particles: usize,
-    ) {
-        let mut rng = SimpleRng::new();
+        let mut near_misses: Vec<(f32, f32)> = vec![];
+        for star in self.big_stars.iter_mut() {
+            let (px, py) = star.project(width, height);
+            let close: bool =
+                distance_squared(px, py, hitbox.x, hitbox.y) < NEAR_MISS_RADIUS * NEAR_MISS_RADIUS;
+            if !star.grazed && star.z < NEAR_MISS_DEPTH && close {
+                star.grazed = true;
+                near_misses.push((px, py));
+            }
+        }
+        for (x, y) in near_misses {
+            let points: usize = self.scoring.near_miss();
+            self.floating_texts.push(FloatingText::new(
+                x,
+                y,
+                format!("CLOSE +{points}"),
+                0x66FFFF,
+            ));
+        }
+    }
 
-        // Movement
-        if window.is_key_down(Key::Right) && self.ship_x < width as i32 - 3 {
-            self.ship_x += 3;
+    // Scores a destroyed star and shows the points where it was. Then leaves whatever it leaves
+    // behind: sometimes a power-up, and shards if it was a splitter. Both change what happens
+    // next, so they come from the gameplay stream. A harmful star the ship `rammed` only counts:
+    // flying into it earns nothing, and a splitter's shards would start inside the ship.
+    fn star_destroyed(&mut self, star: &BigStar, rammed: bool) {
+        let (x, y) = star.project(self.settings.width, self.settings.height);
+        self.collision_count += 1;
+        if rammed && star.kind.contact_damage() > 0 {
+            return;
         }
-        if window.is_key_down(Key::Left) && self.ship_x > 0 {
-            self.ship_x -= 3;
+        let points: usize = self.scoring.hit(star.z, star.kind.worth());
+        let color: u32 = if self.scoring.multiplier() > 1 {
+            0xFFDD33
+        } else {
+            0xFFFFFF
+        };
+        self.floating_texts
+            .push(FloatingText::new(x, y, format!("+{points}"), color));
+
+        if let Some(power_up) = PowerUp::drop(star.x, star.y, star.z, &mut self.rng) {
+            self.power_ups.push(power_up);
         }
-        if window.is_key_down(Key::Down) && self.ship_y < height as i32 - 3 {
-            self.ship_y += 3;
+        if star.kind == StarKind::Splitter {
+            let shards: Vec<BigStar> = star.shards(&mut self.rng);
+            self.big_star_shard_count += shards.len() as u32;
+            self.big_stars.extend(shards);
         }
-        if window.is_key_down(Key::Up) && self.ship_y > 0 {
-            self.ship_y -= 3;
+    }
+
+    pub fn handle_input(&mut self, input: &InputState, audio: &mut dyn AudioSink) {
+        let width: usize = self.settings.width;
+        let height: usize = self.settings.height;
+        let axis: (f32, f32) = input.axis();
+        self.ship.steer(axis, self.dt, width, height);
+        if let Some(puff) = self.ship.exhaust(axis, self.dt, &mut self.fx_rng) {
+            self.particles.push(puff);
         }
 
-        // Spacebar action
-        if window.is_key_down(Key::Space) && self.space_cooldown_timer <= 0.0 {
-            self.space_cooldown_timer = 0.1;
+        self.armory.cycle(input.next_weapon);
+        let was_low: bool = self.energy.is_low();
+        let shots: Vec<Projectile> =
+            self.armory
+                .fire(input.fire, &self.ship, &mut self.energy, self.dt);
+        if !shots.is_empty() {
+            audio.play(SoundEvent::Fire);
+            self.projectiles.extend(shots);
+            if self.energy.overheated {
+                audio.play(SoundEvent::Overheat);
+            } else if self.energy.is_low() && !was_low {
+                audio.play(SoundEvent::LowEnergy);
+            }
+        }
 
-            play_pitched_tone(500.0, 0.35, square_wave, stream_handle);
+        // The bomb: every star within the blast radius goes at once
+        if self.armory.bomb(input.bomb, self.settings.bomb_cooldown) {
+            audio.play(SoundEvent::Fire);
 
             let mut to_explode: Vec<usize> = vec![];
 
+            let radius: f32 = self.blast_radius();
             for (i, star) in self.big_stars.iter_mut().enumerate() {
-                let px: f32 = star.x / star.z * width as f32 / 2.0 + width as f32 / 2.0;
-                let py: f32 = star.y / star.z * height as f32 / 2.0 + height as f32 / 2.0;
+                let (px, py) = star.project(width, height);
 
-                if !star.hit
-                    && distance_squared(px, py, self.ship_x as f32, self.ship_y as f32) < 900.0
+                if !star.hit && distance_squared(px, py, self.ship.x, self.ship.y) < radius * radius
                 {
                     to_explode.push(i);
                 }
@@ -257,66 +462,305 @@ impl GameState {
             for i in to_explode {
                 let star: &mut BigStar = &mut self.big_stars[i];
                 star.hit = true;
+                let star: BigStar = star.clone();
                 self.reset_shake();
-                play_noise_boom(0.1, stream_handle);
+                audio.play(SoundEvent::Explosion);
 
-                self.collision_count += 1;
+                self.star_destroyed(&star, false);
                 println!(
                     "Manual explosion! Total collisions: {}",
                     self.collision_count
                 );
 
-                for _ in 0..num_particles {
-                    let life: u32 = rng.random_range_u32(50..100);
-                    self.particles.push(crate::particles::Particle {
-                        x: self.ship_x as f32,
-                        y: self.ship_y as f32,
-                        vx: rng.random_range_f32(-4.0..4.0),
-                        vy: rng.random_range_f32(-4.0..4.0),
-                        life,
-                        initial_life: life,
-                    });
+                self.spawn_explosion(self.ship.x, self.ship.y);
+            }
+
+            let in_reach: Vec<(usize, f32, f32)> = match &self.boss {
+                Some(boss) => boss
+                    .targets(width, height)
+                    .into_iter()
+                    .filter(|(_, x, y, _)| {
+                        distance_squared(*x, *y, self.ship.x, self.ship.y) < radius * radius
+                    })
+                    .map(|(i, x, y, _)| (i, x, y))
+                    .collect(),
+                None => Vec::new(),
+            };
+            for (i, x, y) in in_reach {
+                self.hit_boss(i, BOMB_DAMAGE, x, y, audio);
+            }
+        }
+    }
+
+    // Moves shots along and damages the big stars they touch. Stars are compared where they
+    // appear on the play field, so a shot hits what it visibly passes over.
+    pub fn update_projectiles(&mut self, audio: &mut dyn AudioSink) {
+        let dt: f32 = self.dt;
+        let width: usize = self.settings.width;
+        let height: usize = self.settings.height;
+        let mut destroyed: Vec<BigStar> = vec![];
+        // Weak point, damage and where it was hit
+        let mut boss_hits: Vec<(usize, u8, f32, f32)> = vec![];
+
+        for shot in self.projectiles.iter_mut() {
+            shot.x += shot.vx * dt;
+            shot.y += shot.vy * dt;
+            shot.life -= dt;
+
+            for star in self.big_stars.iter_mut() {
+                if star.hit || shot.life <= 0.0 {
+                    continue;
+                }
+                let (px, py) = star.project(width, height);
+                let reach: f32 = shot.radius + star.radius();
+                if distance_squared(px, py, shot.x, shot.y) < reach * reach {
+                    if star.damage(shot.damage) {
+                        destroyed.push(star.clone());
+                    }
+                    if !shot.piercing {
+                        shot.life = 0.0;
+                    }
                 }
             }
+
+            if let Some(boss) = &self.boss {
+                for (i, x, y, radius) in boss.targets(width, height) {
+                    let reach: f32 = shot.radius + radius;
+                    if shot.life > 0.0 && distance_squared(x, y, shot.x, shot.y) < reach * reach {
+                        boss_hits.push((i, shot.damage, x, y));
+                        if !shot.piercing {
+                            shot.life = 0.0;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.projectiles.retain(|shot: &Projectile| {
+            let on_field: bool =
+                shot.x >= 0.0 && shot.x <= width as f32 && shot.y >= 0.0 && shot.y <= height as f32;
+            shot.life > 0.0 && on_field
+        });
+
+        for star in destroyed {
+            let (x, y) = star.project(width, height);
+            audio.play(SoundEvent::Explosion);
+            self.star_destroyed(&star, false);
+            self.spawn_explosion(x, y);
+        }
+        for (i, damage, x, y) in boss_hits {
+            self.hit_boss(i, damage, x, y, audio);
+        }
+    }
+
+    // Moves the boss, sends in whatever it attacks with and lets its beams burn the ship
+    pub fn update_boss(&mut self, audio: &mut dyn AudioSink) {
+        let Some(boss) = self.boss.as_mut() else {
+            return;
+        };
+        let stars: Vec<BigStar> = boss.update(self.dt, &mut self.rng);
+        self.big_star_spawned_count += stars.len() as u32;
+        self.big_stars.extend(stars);
+
+        let burned: bool = boss.burns(
+            self.ship.x,
+            self.ship.y,
+            self.settings.width,
+            self.settings.height,
+        );
+        // The shield boost soaks up beams too
+        if burned && !self.boosts.is_active(PowerUpKind::Shield) {
+            self.hurt_ship(BEAM_DAMAGE, audio);
         }
     }
 
-    pub fn update_starfield(&mut self, buffer: &mut [u32], width: usize, height: usize) {
-        draw_stars(&mut self.far_stars, buffer, width, height, 0.003);
-        draw_stars(&mut self.near_stars, buffer, width, height, 0.01);
+    // Takes a hit to the ship. When the hull gives out the ship blows up, and if there's a life
+    // left a new one comes in at the middle.
+    fn hurt_ship(&mut self, damage: usize, audio: &mut dyn AudioSink) {
+        // Nothing can hurt the ship in a mode that can't be lost
+        if !self.mode.can_fail() {
+            return;
+        }
+        let hurt: Hurt = self.health.hurt(damage);
+        match hurt {
+            Hurt::Ignored => {}
+            Hurt::Absorbed => audio.play(SoundEvent::ShieldHit),
+            Hurt::Damaged => {
+                audio.play(SoundEvent::HullHit);
+                self.reset_shake();
+            }
+            Hurt::LifeLost | Hurt::Destroyed => {
+                audio.play(SoundEvent::ShipLost);
+                self.reset_shake();
+                for _ in 0..3 {
+                    self.spawn_explosion(self.ship.x, self.ship.y);
+                }
+                if hurt == Hurt::LifeLost {
+                    self.ship = Ship::new(
+                        (self.settings.width / 2) as f32,
+                        (self.settings.height / 2) as f32,
+                    );
+                }
+            }
+        }
     }
 
-    pub fn update_big_stars(&mut self, buffer: &mut [u32], width: usize, height: usize) -> usize {
-        let mut rng: SimpleRng = SimpleRng::new();
-        let mut missed_this_frame: usize = 0;
-        
-        let seconds: f64 = self.total_seconds as f64;
-        let stars_per_second: f64 = 0.01 + (seconds / 1_000_000.0); // Very slow ramp-up
-        let expected_total: u32 = (seconds * stars_per_second).floor() as u32;
-
-        // Occasionally spawn a new big star
-        // if rng.random_ratio(1, 200) {
-        //     self.big_stars.push(BigStar {
-        //         x: rng.random_range(-1.5..1.5),
-        //         y: rng.random_range(-1.5..1.5),
-        //         z: 2.5 + rng.random_range(0.0..1.0),
-        //         hit: false,
-        //         was_missed: false,
-        //     });
-        // }
-        while self.big_star_spawned_count < expected_total {
-            self.big_stars.push(BigStar {
-                x: rng.random_range_f32(-1.5..1.5),
-                y: rng.random_range_f32(-1.5..1.5),
-                z: 2.5 + rng.random_range_f32(0.0..1.0),
-                hit: false,
-                was_missed: false,
+    // Damages weak point `index` of the boss, hit at (x, y) on the play field
+    fn hit_boss(&mut self, index: usize, damage: u8, x: f32, y: f32, audio: &mut dyn AudioSink) {
+        let Some(boss) = self.boss.as_mut() else {
+            return;
+        };
+        let result: Damage = boss.damage(index, damage);
+        let z: f32 = boss.z;
+        let point_down: bool = boss.weak_points[index].health == 0;
+        let (boss_x, boss_y) = boss.project(self.settings.width, self.settings.height);
+        if result == Damage::Dented {
+            return;
+        }
+
+        if point_down {
+            let points: usize = self.scoring.hit(z, WEAK_POINT_WORTH);
+            self.floating_texts
+                .push(FloatingText::new(x, y, format!("+{points}"), 0xFFDD33));
+            audio.play(SoundEvent::Explosion);
+            self.spawn_explosion(x, y);
+        }
+        match result {
+            Damage::NextPhase => {
+                audio.play(SoundEvent::BossPhase);
+                self.reset_shake();
+            }
+            Damage::Defeated => {
+                let points: usize = self.scoring.hit(z, BOSS_WORTH);
+                self.floating_texts.push(FloatingText::new(
+                    boss_x,
+                    boss_y,
+                    format!("+{points}"),
+                    0xFFDD33,
+                ));
+                audio.play(SoundEvent::BossDefeated);
+                self.reset_shake();
+                for _ in 0..3 {
+                    self.spawn_explosion(boss_x, boss_y);
+                }
+                self.boss = None;
+            }
+            _ => {}
+        }
+    }
+
+    // How far a bomb reaches; the wide blast power-up doubles it
+    fn blast_radius(&self) -> f32 {
+        if self.boosts.is_active(PowerUpKind::WideBlast) {
+            self.settings.bomb_radius * 2.0
+        } else {
+            self.settings.bomb_radius
+        }
+    }
+
+    // Drifts pickups towards the camera and collects the ones the ship flies into
+    pub fn update_power_ups(&mut self, audio: &mut dyn AudioSink) {
+        let width: usize = self.settings.width;
+        let height: usize = self.settings.height;
+        let mut collected: Vec<PowerUpKind> = vec![];
+
+        self.power_ups.retain_mut(|power_up: &mut PowerUp| {
+            power_up.drift(self.dt);
+            let (px, py) = power_up.project(width, height);
+            if distance_squared(px, py, self.ship.x, self.ship.y) < PICKUP_RADIUS * PICKUP_RADIUS {
+                collected.push(power_up.kind);
+                return false;
+            }
+            let off_screen: bool = px < 0.0 || px > width as f32 || py < 0.0 || py > height as f32;
+            power_up.z > 0.1 && !off_screen
+        });
+
+        for kind in collected {
+            audio.play(SoundEvent::PowerUp);
+            if kind == PowerUpKind::ExtraLife {
+                self.health.add_life();
+                self.redemption_flash_timer = 0.3;
+            } else {
+                self.boosts.activate(kind);
+            }
+        }
+    }
+
+    fn spawn_explosion(&mut self, x: f32, y: f32) {
+        for _ in 0..self.settings.particles {
+            let life: f32 = self.fx_rng.random_range_f32(50.0 / 60.0..100.0 / 60.0);
+            self.particles.push(Particle {
+                x,
+                y,
+                vx: self
+                    .fx_rng
+                    .random_range_f32(-PARTICLE_SPEED..PARTICLE_SPEED),
+                vy: self
+                    .fx_rng
+                    .random_range_f32(-PARTICLE_SPEED..PARTICLE_SPEED),
+                life,
+                initial_life: life,
             });
-            self.big_star_spawned_count += 1;
         }
+    }
+
+    pub fn update_starfield(&mut self) {
+        let dt: f32 = self.dt;
+        advance_stars(&mut self.far_stars, &mut self.fx_rng, FAR_STAR_SPEED * dt);
+        advance_stars(&mut self.near_stars, &mut self.fx_rng, NEAR_STAR_SPEED * dt);
+        advance_stars(&mut self.stars, &mut self.fx_rng, STAR_SPEED * dt);
+    }
+
+    // Whatever the current wave (or the endless stream) sends this tick
+    pub fn spawn_big_stars(&mut self, audio: &mut dyn AudioSink) {
+        let field_clear: bool = self.boss.is_none() && self.big_stars.iter().all(|star| star.hit);
+        let progress: Progress = self.campaign.tick(
+            self.dt,
+            field_clear,
+            self.scoring.score,
+            &self.settings,
+            &mut self.rng,
+        );
+        if progress.wave_cleared {
+            audio.play(SoundEvent::WaveCleared);
+        }
+        // One boss at a time; in endless play a late one is simply skipped
+        if let Some(kind) = progress.boss
+            && self.boss.is_none()
+        {
+            self.boss = Some(Boss::new(kind));
+            audio.play(SoundEvent::BossArrives);
+        }
+        self.big_star_spawned_count += progress.spawned.len() as u32;
+        self.big_stars.extend(progress.spawned);
+    }
+
+    pub fn update_big_stars(&mut self) -> usize {
+        let width: usize = self.settings.width;
+        let height: usize = self.settings.height;
+        let rng: &mut SimpleRng = &mut self.rng;
+        let mut missed_this_frame: usize = 0;
+        let endless: bool = self.campaign.is_endless();
+
+        let time_slow: f32 = if self.boosts.is_active(PowerUpKind::TimeSlow) {
+            0.5
+        } else {
+            1.0
+        };
+        let approach: f32 = time_slow * self.campaign.speed();
+        let magnet: bool = self.boosts.is_active(PowerUpKind::Magnet);
+        // The ship's position as a star at depth 1 would need to be to project onto it
+        let ship_x: f32 = (self.ship.x - width as f32 / 2.0) / (width as f32 / 2.0);
+        let ship_y: f32 = (self.ship.y - height as f32 / 2.0) / (height as f32 / 2.0);
 
         for star in self.big_stars.iter_mut() {
-            star.z -= rng.random_range_f32(0.002..0.006);
+            let depth: f32 = rng.random_range_f32(0.12..0.36) * self.dt * approach;
+            star.advance(depth, ship_x, ship_y, self.dt);
+            if magnet && !star.hit {
+                let pull: f32 = (MAGNET_PULL * self.dt).min(1.0);
+                star.x += (ship_x * star.z - star.x) * pull;
+                star.y += (ship_y * star.z - star.y) * pull;
+            }
 
             // Out of bounds or too close
             let off_screen: bool = star.is_off_screen(width, height);
@@ -324,76 +768,169 @@ impl GameState {
 
             if off_screen || too_close {
                 if !star.hit && !star.was_missed {
-                    missed_this_frame += 1;
+                    missed_this_frame += star.kind.miss_penalty();
                     star.was_missed = true;
                 }
-                // Respawn
-                star.x = rng.random_range_f32(-1.5..1.5);
-                star.y = rng.random_range_f32(-1.5..1.5);
-                star.z = 2.5 + rng.random_range_f32(0.0..1.0);
-                star.hit = false;
-                star.was_missed = false;
-                continue;
+                // Endless play keeps the field topped up; in a level only the waves send stars
+                if endless {
+                    *star = BigStar::spawn(rng);
+                }
             }
+        }
 
-            let sx: isize = (star.x / star.z * width as f32 / 2.0 + width as f32 / 2.0) as isize;
-            let sy: isize = (star.y / star.z * height as f32 / 2.0 + height as f32 / 2.0) as isize;
+        if !endless {
+            let before: usize = self.big_stars.len();
+            self.big_stars.retain(|star| !star.was_missed);
+            self.big_star_escaped_count += (before - self.big_stars.len()) as u32;
+        }
 
+        missed_this_frame
+    }
+
+    pub fn update_particles(&mut self) {
+        let dt: f32 = self.dt;
+        self.particles.retain_mut(|p: &mut Particle| {
+            p.x += p.vx * dt;
+            p.y += p.vy * dt;
+            p.life = (p.life - dt).max(0.0);
+            p.life > 0.0
+        });
+    }
+
+    fn render_big_stars(&self, canvas: &mut Canvas, view: View, ship_x: f32, ship_y: f32) {
+        let width: usize = canvas.width;
+        let height: usize = canvas.height;
+        let field_width: f32 = self.settings.width as f32;
+        let field_height: f32 = self.settings.height as f32;
+        // The sparkle changes every tick but is the same however often a tick is redrawn
+        let mut sparkle: SimpleRng = SimpleRng::new(self.seed ^ self.replay.frames.len() as u64);
+
+        for star in self.big_stars.iter() {
             let px: f32 = star.x / star.z * width as f32 / 2.0 + width as f32 / 2.0;
             let py: f32 = star.y / star.z * height as f32 / 2.0 + height as f32 / 2.0;
-            let dist2: f32 = distance_squared(px, py, self.ship_x as f32, self.ship_y as f32);
-            let proximity_sq: f32 = 900.0;
+            let sx: isize = px as isize;
+            let sy: isize = py as isize;
+
+            // Reach is measured on the play field, the same way firing does it
+            let field_x: f32 = star.x / star.z * field_width / 2.0 + field_width / 2.0;
+            let field_y: f32 = star.y / star.z * field_height / 2.0 + field_height / 2.0;
+            let dist2: f32 = distance_squared(field_x, field_y, ship_x, ship_y);
+            let proximity_sq: f32 = self.blast_radius() * self.blast_radius();
 
             if dist2 < proximity_sq && !star.hit {
                 // Draw red outline box
-                let red: u32 = 0xFF0000;
-                for oy in -4..=4 {
-                    for ox in -4..=4 {
-                        if (ox as isize).abs() == 4 || (oy as isize).abs() == 4 {
-                            let bx: isize = sx + ox;
-                            let by: isize = sy + oy;
-                            if bx >= 0 && bx < width as isize && by >= 0 && by < height as isize {
-                                let idx: usize = by as usize * width + bx as usize;
-                                buffer[idx] = red;
-                            }
-                        }
-                    }
-                }
+                let half: isize = (4.0 * view.size).round() as isize;
+                let side: usize = (half * 2 + 1) as usize;
+                canvas.rect(sx - half, sy - half, side, side, 0xFF0000);
             }
 
             // Draw the big star (a 3x3 or blended blob)
             if sx >= 1 && sx < width as isize - 1 && sy >= 1 && sy < height as isize - 1 {
-                let size: isize = ((1.0 / star.z) * 10.0).clamp(1.0, 6.0) as isize;
-
-                for dy in -size..=size {
-                    for dx in -size..=size {
-                        if dx*dx + dy*dy <= size*size {
-                            let px: isize = sx + dx;
-                            let py: isize = sy + dy;
-                            if px >= 0 && px < width as isize && py >= 0 && py < height as isize {
-                                let idx: usize = py as usize * width + px as usize;
-                                buffer[idx] = generate_big_star_color();
-                            }
-                        }
+                let size: isize = (star.radius() * view.size).max(1.0) as isize;
+                canvas
+                    .filled_circle_with(sx, sy, size, |_, _| generate_big_star_color(&mut sparkle));
+                draw_star_marking(canvas, star, sx, sy, size, view.size);
+            }
+        }
+    }
+
+    fn render_projectiles(&self, canvas: &mut Canvas, view: View, lag: f32) {
+        let pixel: usize = view.size.round().max(1.0) as usize;
+        for shot in self.projectiles.iter() {
+            let field_x: f32 = shot.x - shot.vx * lag;
+            let field_y: f32 = shot.y - shot.vy * lag;
+            let (x, y) = view.point(field_x, field_y);
+            let cx: isize = x.round() as isize;
+            let cy: isize = y.round() as isize;
+            match shot.weapon {
+                Weapon::Pulse => {
+                    // A short streak trailing back along the shot's path
+                    let speed: f32 = (shot.vx * shot.vx + shot.vy * shot.vy).sqrt();
+                    for step in 0..6 {
+                        let back: f32 = step as f32 / speed;
+                        let (x, y) = view.point(field_x - shot.vx * back, field_y - shot.vy * back);
+                        canvas.fill_rect(x as isize, y as isize, pixel, pixel, 0x66FFFF);
                     }
                 }
+                Weapon::Spread => {
+                    let radius: isize = (view.size.round() as isize).max(1);
+                    canvas.filled_circle(cx, cy, radius, 0xFFDD55);
+                }
+                Weapon::Beam => {
+                    let radius: isize = (shot.radius * view.size).round() as isize;
+                    canvas.filled_circle(cx, cy, radius, 0xFF66FF);
+                    canvas.filled_circle(cx, cy, radius / 2, 0xFFFFFF);
+                }
             }
         }
 
-        missed_this_frame
+        // The beam's charge glows at the ship's nose while fire is held
+        if self.armory.selected == Weapon::Beam && self.armory.charge > 0.0 {
+            let (x, y) = view.point(self.ship.x, self.ship.y - 8.0);
+            let radius: f32 = 1.0 + 4.0 * self.armory.charge / FULL_CHARGE;
+            canvas.circle(
+                x.round() as isize,
+                y.round() as isize,
+                (radius * view.size).round() as isize,
+                0xFF66FF,
+            );
+        }
     }
 
-    pub fn update_particles(&mut self, buffer: &mut [u32], width: usize, height: usize) {
-        self.particles.retain_mut(|p: &mut Particle| {
-            p.x += p.vx;
-            p.y += p.vy;
-            p.life = p.life.saturating_sub(1);
+    fn render_power_ups(&self, canvas: &mut Canvas, view: View) {
+        let letter_scale: usize = view.size.round().max(1.0) as usize;
+        for power_up in self.power_ups.iter() {
+            let (px, py) = power_up.project(canvas.width, canvas.height);
+            let radius: f32 = ((1.0 / power_up.z) * 6.0).clamp(5.0, 9.0) * view.size;
+            let (cx, cy) = (px.round() as isize, py.round() as isize);
+            canvas.filled_circle(cx, cy, radius.round() as isize, power_up.kind.color());
+            canvas.circle(cx, cy, radius.round() as isize + 1, 0xFFFFFF);
+
+            // The glyph is 5x7 cells
+            let letter: String = power_up.kind.letter().to_string();
+            let x: isize = cx - (5 * letter_scale / 2) as isize;
+            let y: isize = cy - (7 * letter_scale / 2) as isize;
+            if x >= 0 && y >= 0 {
+                draw_text(
+                    canvas,
+                    x as usize,
+                    y as usize,
+                    &letter,
+                    0x000000,
+                    letter_scale,
+                );
+            }
+        }
+    }
 
-            if p.x >= 1.0 && p.x < (width - 1) as f32 && p.y >= 1.0 && p.y < (height - 1) as f32 {
-                let cx: usize = p.x as usize;
-                let cy: usize = p.y as usize;
-                let base_r: f32 = 255.0 * (p.life as f32 / p.initial_life as f32);
-                let base_g: f32 = 170.0 * (p.life as f32 / p.initial_life as f32);
+    fn render_floating_texts(&self, canvas: &mut Canvas, view: View) {
+        let scale: usize = (2.0 * view.size).round().max(1.0) as usize;
+        for text in self.floating_texts.iter() {
+            let (x, y) = view.point(text.x, text.y);
+            let fade: f32 = text.fade();
+            let channel = |shift: u32| (((text.color >> shift) & 0xFF) as f32 * fade) as u32;
+            let color: u32 = channel(16) << 16 | channel(8) << 8 | channel(0);
+            // Centred on the impact point
+            let left: f32 = x - text_width(&text.text, scale) as f32 / 2.0;
+            if left >= 0.0 && y >= 0.0 {
+                draw_text(canvas, left as usize, y as usize, &text.text, color, scale);
+            }
+        }
+    }
+
+    fn render_particles(&self, canvas: &mut Canvas, view: View, lag: f32) {
+        let width: usize = canvas.width;
+        let height: usize = canvas.height;
+
+        for p in self.particles.iter() {
+            // Step back along the velocity to where the particle was at this point of the tick
+            let (x, y) = view.point(p.x - p.vx * lag, p.y - p.vy * lag);
+
+            if x >= 1.0 && x < (width - 1) as f32 && y >= 1.0 && y < (height - 1) as f32 {
+                let cx: isize = x as isize;
+                let cy: isize = y as isize;
+                let base_r: f32 = 255.0 * (p.life / p.initial_life);
+                let base_g: f32 = 170.0 * (p.life / p.initial_life);
 
                 let positions = [
                     (0, 0, 1.0),   // center
@@ -407,132 +944,986 @@ impl GameState {
                     (1, 1, 0.2),
                 ];
 
+                let color: u32 = blend_color(base_r as u8, base_g as u8, 0, 1.0);
                 for (dx, dy, alpha) in positions {
-                    let px: isize = cx as isize + dx;
-                    let py: isize = cy as isize + dy;
-                    if px >= 0 && px < width as isize && py >= 0 && py < height as isize {
-                        let index: usize = py as usize * width + px as usize;
-                        let color: u32 = blend_color(base_r as u8, base_g as u8, 0, alpha as f32);
-                        buffer[index] = color;
-                    }
+                    canvas.blend_pixel(cx + dx, cy + dy, color, alpha);
                 }
             }
-
-            p.life > 0
-        });
+        }
     }
 
-    pub fn flash(
-        &mut self, 
-        buffer: &mut [u32], 
-        draw_x: isize, 
-        draw_y: isize, 
-        width: usize, 
-        height: usize, 
-        color: u32,
-    ) {
-        let center_x: usize = draw_x as usize;
-        let center_y: usize = draw_y as usize;
-        let radius: i32 = (30.0 * (1.0 - self.redemption_flash_timer / 0.7)) as i32;
-
-        for angle in (0..360).step_by(3) {
-            let rad: f32 = (angle as f32).to_radians();
-            let x: isize = center_x as isize + (radius as f32 * rad.cos()) as isize;
-            let y: isize = center_y as isize + (radius as f32 * rad.sin()) as isize;
-
-            if x >= 0 && x < width as isize && y >= 0 && y < height as isize {
-                let idx: usize = y as usize * width + x as usize;
-
-                let fade: u8 = ((self.redemption_flash_timer / 0.3) * 255.0) as u8;
-                match color {
-                    0xFF0000 => {
-                        buffer[idx] = (fade as u32) << 16; // Red with fade
-                    }
-                    0x00FF00 => {
-                        buffer[idx] = (fade as u32) << 8; // Green with fade
-                    }
-                    0x0000FF => {
-                        buffer[idx] = fade as u32; // Blue with fade
-                    }
-                    _ => {
-                        buffer[idx] = 0xFFFFFF; // Default to white if color not recognized
-                    }
-                    
-                }
-            }
-        }
+    pub fn flash(&self, canvas: &mut Canvas, draw_x: isize, draw_y: isize, color: u32, size: f32) {
+        let radius: i32 = (30.0 * size * (1.0 - self.redemption_flash_timer / 0.7)) as i32;
+        let fade: u8 = ((self.redemption_flash_timer / 0.3) * 255.0) as u8;
+        let faded: u32 = match color {
+            0xFF0000 => (fade as u32) << 16, // Red with fade
+            0x00FF00 => (fade as u32) << 8,  // Green with fade
+            0x0000FF => fade as u32,         // Blue with fade
+            _ => 0xFFFFFF,                   // Default to white if color not recognized
+        };
+
+        canvas.circle(draw_x, draw_y, radius as isize, faded);
     }
 
-    pub fn check_and_shake(
-        &mut self, 
-        stream_handle: &rodio::OutputStreamHandle,
-        width: usize,
-        height: usize,
-        num_particles: usize,
-    ) {
-        let mut rng = SimpleRng::new();
+    pub fn check_and_shake(&mut self, audio: &mut dyn AudioSink) {
+        let rng: &mut SimpleRng = &mut self.rng;
         let mut shake_x: i32 = 0;
         let mut shake_y: i32 = 0;
-        if self.screen_shake_timer > 0 {
+        if self.screen_shake_timer > 0.0 {
             shake_x = rng.random_range_f32(-2.0..2.0) as i32;
             shake_y = rng.random_range_f32(-2.0..2.0) as i32;
-            self.screen_shake_timer -= 1;
+            self.screen_shake_timer -= self.dt;
         }
 
         // --- Check collisions with big stars ---
-        self.check_collisions(
-            &stream_handle,
-            width,
-            height,
-            num_particles,
-            shake_x as f32,
-            shake_y as f32,
-        );
+        self.check_collisions(audio, shake_x as f32, shake_y as f32);
     }
 
     pub fn reset_shake(&mut self) {
-        self.screen_shake_timer = 10;
+        self.screen_shake_timer = SCREEN_SHAKE_TIME;
         self.shake_duration = 0.5;
         self.shake_timer = self.shake_duration;
     }
+}
 
-    pub fn _shake_offsets(&mut self) -> (f32, f32) {
-        if self.shake_timer <= 0.0 {
-            return (0.0, 0.0);
+// Maps play-field coordinates onto a canvas of any size. The field is stretched to fill the
+// canvas, which keeps everything lined up with the starfield projection; sizes scale with the
+// smaller of the two stretches so round things stay round.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct View {
+    scale_x: f32,
+    scale_y: f32,
+    size: f32,
+}
+
+impl View {
+    fn new(settings: &Settings, canvas: &Canvas) -> Self {
+        let scale_x: f32 = canvas.width as f32 / settings.width as f32;
+        let scale_y: f32 = canvas.height as f32 / settings.height as f32;
+        Self {
+            scale_x,
+            scale_y,
+            size: scale_x.min(scale_y),
         }
+    }
 
-        let progress: f32 = 1.0 - (self.shake_timer / self.shake_duration);
-        let amplitude: f32 = 5.0 * (1.0 - progress);
-        let frequency: f32 = 30.0;
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.scale_x, y * self.scale_y)
+    }
+}
 
-        self.shake_timer -= 1.0 / 60.0;
+// Hull, weak points and beams. Like the big stars it's projected straight onto the canvas.
+fn render_boss(canvas: &mut Canvas, view: View, boss: &Boss) {
+    let (x, y) = boss.project(canvas.width, canvas.height);
+    let (cx, cy) = (x as isize, y as isize);
+
+    // Beams first, so the hull sits on top of where they start
+    let length: f32 = (canvas.width + canvas.height) as f32;
+    for beam in boss.beams.iter() {
+        let (dx, dy) = beam.direction();
+        let end_x: isize = (x + dx * length) as isize;
+        let end_y: isize = (y + dy * length) as isize;
+        if !beam.is_burning() {
+            canvas.line(cx, cy, end_x, end_y, 0x884444);
+            continue;
+        }
+        // Parallel lines across the burning width, hot in the middle
+        let half: isize = (BEAM_WIDTH * view.size).round().max(1.0) as isize;
+        // in half-pixel steps so a slanted beam has no gaps
+        for step in -2 * half..=2 * half {
+            let color: u32 = if step.abs() <= 2 * half / 3 {
+                0xFFFFFF
+            } else {
+                0xFF4444
+            };
+            let offset: f32 = step as f32 / 2.0;
+            let (ox, oy) = (
+                (offset * dy).round() as isize,
+                (-offset * dx).round() as isize,
+            );
+            canvas.line(cx + ox, cy + oy, end_x + ox, end_y + oy, color);
+        }
+    }
 
-        (
-            (progress * frequency * std::f32::consts::TAU).sin() * amplitude,
-            ((progress * frequency + 0.5) * std::f32::consts::TAU).sin() * amplitude,
-        )
+    let hull: isize = boss.hull_radius(canvas.width) as isize;
+    let blink: bool = boss.shielded > 0.0 && ((boss.shielded * 10.0) as usize).is_multiple_of(2);
+    let color: u32 = if boss.flash > 0.0 || blink {
+        0xFFFFFF
+    } else {
+        boss.kind.color()
+    };
+    canvas.filled_circle(cx, cy, hull, scale_color(color, 0.5));
+    canvas.circle(cx, cy, hull, color);
+
+    // Weak points fade as they're worn down and go dark when destroyed
+    let size: isize = (boss.weak_point_radius(canvas.width) as isize).max(2);
+    for point in boss.weak_points.iter() {
+        let (px, py) = boss.weak_point_position(point, canvas.width, canvas.height);
+        let (px, py) = (px as isize, py as isize);
+        if point.health == 0 {
+            canvas.circle(px, py, size, 0x444444);
+        } else {
+            let strength: f32 = point.health as f32 / boss.toughness() as f32;
+            canvas.filled_circle(px, py, size, scale_color(0xFF3300, 0.4 + 0.6 * strength));
+        }
     }
 }
 
-fn draw_stars(stars: &mut Vec<Star>, buffer: &mut [u32], width: usize, height: usize, speed: f32) {
-    let mut rng = SimpleRng::new();
+fn scale_color(color: u32, alpha: f32) -> u32 {
+    blend_color((color >> 16) as u8, (color >> 8) as u8, color as u8, alpha)
+}
+
+// What sets the kinds of big star apart on screen, drawn around a blob of radius `size`
+fn draw_star_marking(
+    canvas: &mut Canvas,
+    star: &BigStar,
+    sx: isize,
+    sy: isize,
+    size: isize,
+    scale: f32,
+) {
+    let gap: isize = scale.round().max(1.0) as isize;
+    match star.kind {
+        StarKind::Normal | StarKind::Shard => {}
+        // One ring of plating per hit it can still take
+        StarKind::Armored => {
+            for ring in 0..star.hits_left as isize {
+                canvas.circle(sx, sy, size + gap * (1 + 2 * ring), 0xAAAAAA);
+            }
+        }
+        StarKind::Splitter => canvas.circle(sx, sy, size + gap, 0xFF8800),
+        StarKind::Drifter => canvas.circle(sx, sy, size + gap, 0x66FF66),
+        // A tail pointing back to the middle of the screen, where it came from
+        StarKind::Streaker => {
+            let (cx, cy) = (canvas.width as f32 / 2.0, canvas.height as f32 / 2.0);
+            let (dx, dy) = (sx as f32 - cx, sy as f32 - cy);
+            let length: f32 = (dx * dx + dy * dy).sqrt().max(1.0);
+            let tail: f32 = (size * 4) as f32 + 6.0 * scale;
+            let end_x: isize = (sx as f32 - dx / length * tail) as isize;
+            let end_y: isize = (sy as f32 - dy / length * tail) as isize;
+            canvas.line(sx, sy, end_x, end_y, 0x88CCFF);
+        }
+        // Crosshair ticks: it's coming for the ship
+        StarKind::Homing => {
+            let (near, far) = (size + gap, size + 3 * gap);
+            canvas.line(sx - far, sy, sx - near, sy, 0xFF3333);
+            canvas.line(sx + near, sy, sx + far, sy, 0xFF3333);
+            canvas.line(sx, sy - far, sx, sy - near, 0xFF3333);
+            canvas.line(sx, sy + near, sx, sy + far, 0xFF3333);
+        }
+    }
+}
 
+fn advance_stars(stars: &mut [Star], rng: &mut SimpleRng, distance: f32) {
     for star in stars.iter_mut() {
-        star.z -= speed;
+        star.z -= distance;
 
         if star.z <= 0.01 {
             star.x = rng.random_range_f32(-1.0..1.0);
             star.y = rng.random_range_f32(-1.0..1.0);
             star.z = 1.0;
         }
+    }
+}
+
+// `behind` pushes the stars back towards where they were earlier in the tick
+fn draw_stars(stars: &[Star], canvas: &mut Canvas, behind: f32) {
+    let width: f32 = canvas.width as f32;
+    let height: f32 = canvas.height as f32;
+
+    for star in stars.iter() {
+        let z: f32 = star.z + behind;
+
+        // Project 3D -> 2D
+        let sx: isize = (star.x / z * width / 2.0 + width / 2.0) as isize;
+        let sy: isize = (star.y / z * height / 2.0 + height / 2.0) as isize;
+
+        let brightness: u32 = ((1.0 - z.min(1.0)) * 255.0) as u32;
+        let color: u32 = (brightness << 16) | (brightness << 8) | brightness;
+        canvas.put_pixel(sx, sy, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boss::{Beam, BossKind};
+    use crate::game_mode::TIME_ATTACK_SECONDS;
+    use crate::health::MAX_LIVES;
+    use crate::space_objects::SHIP_MAX_SPEED;
+
+    const WIDTH: usize = 800;
+    const HEIGHT: usize = 600;
+    const NUM_PARTICLES: usize = 40;
+
+    #[derive(Default)]
+    struct RecordingAudio {
+        events: Vec<SoundEvent>,
+    }
 
-        let sx: isize = (star.x / star.z * width as f32 / 2.0 + width as f32 / 2.0) as isize;
-        let sy: isize = (star.y / star.z * height as f32 / 2.0 + height as f32 / 2.0) as isize;
+    impl AudioSink for RecordingAudio {
+        fn play(&mut self, event: SoundEvent) {
+            self.events.push(event);
+        }
+    }
+
+    // The default settings with a given number of background stars
+    fn with_stars(stars: usize) -> Settings {
+        Settings {
+            stars,
+            ..Settings::default()
+        }
+    }
+
+    fn bomb() -> InputState {
+        InputState {
+            bomb: true,
+            ..InputState::default()
+        }
+    }
+
+    fn count(events: &[SoundEvent], event: SoundEvent) -> usize {
+        events.iter().filter(|e| **e == event).count()
+    }
+
+    #[test]
+    fn test_headless_run_accounts_for_every_spawned_star() {
+        let mut game = GameState::new(&with_stars(100), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        let idle = InputState::default();
+
+        for _ in 0..5000 {
+            game.step(&idle, &mut audio);
+        }
+
+        assert!((game.elapsed_seconds - 5000.0 / 60.0).abs() < 1e-3);
+        assert!(game.big_star_spawned_count > 0);
+        // Stars only leave the field by being hit or getting away
+        assert!(game.big_star_escaped_count > 0);
+        assert_eq!(
+            game.big_stars.len() + game.collision_count + game.big_star_escaped_count as usize,
+            (game.big_star_spawned_count + game.big_star_shard_count) as usize
+        );
+        assert!(game.missed_count > 0);
+        let missed_sounds = count(&audio.events, SoundEvent::Missed);
+        // Catching a harmless star takes a miss back
+        assert!(missed_sounds > 0 && missed_sounds <= game.missed_count + game.collision_count);
+        assert_eq!(
+            count(&audio.events, SoundEvent::Collision),
+            game.collision_count
+        );
+    }
+
+    #[test]
+    fn test_levels_open_with_a_banner_then_send_waves() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        assert_eq!(game.campaign.banner()[0], "LEVEL 1");
+        while game.big_stars.is_empty() {
+            game.step(&InputState::default(), &mut audio);
+        }
+        assert!(game.campaign.banner().is_empty());
+        assert!(game.elapsed_seconds > 2.0);
+        // The first wave opens with three normal stars in a line
+        assert_eq!(game.big_stars.len(), 3);
+        assert!(game.big_stars.iter().all(|s| s.kind == StarKind::Normal));
+    }
 
-        if sx >= 0 && sx < width as isize && sy >= 0 && sy < height as isize {
-            let brightness: u32 = ((1.0 - star.z.min(1.0)) * 255.0) as u32;
-            let color: u32 = (brightness << 16) | (brightness << 8) | brightness;
-            buffer[sy as usize * width + sx as usize] = color;
+    #[test]
+    fn test_endless_play_replaces_missed_stars() {
+        let settings = Settings {
+            start_level: 99,
+            ..with_stars(10)
+        };
+        let mut game = GameState::new(&settings, 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        assert!(game.campaign.is_endless());
+        for _ in 0..3000 {
+            game.step(&InputState::default(), &mut audio);
         }
+        assert!(game.missed_count > 0);
+        assert_eq!(game.big_star_escaped_count, 0);
+        assert_eq!(
+            game.big_stars.len() + game.collision_count,
+            (game.big_star_spawned_count + game.big_star_shard_count) as usize
+        );
+    }
+
+    #[test]
+    fn test_same_seed_replays_identically() {
+        let mut first = GameState::new(&with_stars(100), 99, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut second = GameState::new(&with_stars(100), 99, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+
+        for frame in 0..3000 {
+            // Sweep around and fire now and then so every code path draws randomness
+            let x: f32 = if frame % 400 < 200 { -1.0 } else { 1.0 };
+            let y: f32 = if frame % 300 < 150 { -1.0 } else { 0.5 };
+            let input = InputState::new((x, y), frame % 50 == 0);
+            first.step(&input, &mut audio);
+            second.step(&input, &mut audio);
+        }
+
+        assert_eq!(first.collision_count, second.collision_count);
+        assert_eq!(first.missed_count, second.missed_count);
+        assert_eq!(first.big_stars.len(), second.big_stars.len());
+        for (a, b) in first.big_stars.iter().zip(second.big_stars.iter()) {
+            assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+        }
+    }
+
+    #[test]
+    fn test_replay_reproduces_result() {
+        let mut original =
+            GameState::new(&with_stars(100), 2024, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+
+        let mut frame: usize = 0;
+        while !original.is_game_over() && frame < 100_000 {
+            let x: f32 = if frame % 240 < 120 { 1.0 } else { -1.0 };
+            let y: f32 = if frame % 500 < 250 { 1.0 } else { -1.0 };
+            let input = InputState::new((x, y), frame.is_multiple_of(20));
+            original.step(&input, &mut audio);
+            frame += 1;
+        }
+        assert!(original.is_game_over());
+        let recording = Replay::from_bytes(&original.finished_replay().to_bytes()).unwrap();
+
+        let mut playback = GameState::new(
+            &recording.settings,
+            recording.seed,
+            recording.tick_rate,
+            recording.mode,
+        );
+        for input in &recording.frames {
+            playback.step(input, &mut audio);
+        }
+
+        assert_eq!(recording.result, Some(playback.result()));
+        assert_eq!(playback.game_over_frame, Some(frame));
+    }
+
+    #[test]
+    fn test_bomb_destroys_star_in_blast_radius() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // Projects 20px to the right of the ship, inside the 30px blast radius
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.1, 0.0, 2.0));
+
+        game.handle_input(&bomb(), &mut audio);
+
+        assert_eq!(game.collision_count, 1);
+        assert!(game.big_stars[0].hit);
+        assert_eq!(game.particles.len(), NUM_PARTICLES);
+        assert_eq!(audio.events, vec![SoundEvent::Fire, SoundEvent::Explosion]);
+        assert_eq!(game.armory.bombs, 2);
+    }
+
+    #[test]
+    fn test_collisions_use_ship_hitbox() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // Project to 2px and 5px right of the ship; the hitbox is 3px either side
+        for x in [0.005, 0.0125] {
+            game.big_stars
+                .push(BigStar::new(StarKind::Normal, x, 0.0, 1.0));
+        }
+
+        game.check_collisions(&mut audio, 0.0, 0.0);
+
+        assert_eq!(game.collision_count, 1);
+        assert_eq!(game.big_stars.len(), 1);
+        assert_eq!(game.big_stars[0].x, 0.0125);
+    }
+
+    #[test]
+    fn test_bomb_radius_comes_from_settings() {
+        let settings = Settings {
+            bomb_radius: 10.0,
+            ..with_stars(10)
+        };
+        let mut game = GameState::new(&settings, 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // 20px right of the ship: inside the default 30px radius, outside 10px
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.1, 0.0, 2.0));
+
+        game.handle_input(&bomb(), &mut audio);
+
+        assert_eq!(game.collision_count, 0);
+        assert_eq!(count(&audio.events, SoundEvent::Fire), 1);
+    }
+
+    #[test]
+    fn test_projectile_destroys_star_it_reaches() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // Straight above the ship, 150px away: a pulse gets there in a quarter second
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.0, -0.5, 1.0));
+        // Off to the side, out of the line of fire
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.5, -0.5, 1.0));
+
+        game.handle_input(&InputState::new((0.0, 0.0), true), &mut audio);
+        assert_eq!(game.projectiles.len(), 1);
+        for _ in 0..20 {
+            game.update_projectiles(&mut audio);
+        }
+
+        assert_eq!(game.collision_count, 1);
+        assert!(game.big_stars[0].hit && !game.big_stars[1].hit);
+        assert!(game.projectiles.is_empty());
+        assert_eq!(count(&audio.events, SoundEvent::Explosion), 1);
+    }
+
+    #[test]
+    fn test_armored_soaks_shots_and_splitters_split() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.big_stars
+            .push(BigStar::new(StarKind::Armored, 0.0, -0.5, 1.0));
+        game.big_stars
+            .push(BigStar::new(StarKind::Splitter, 0.5, -0.5, 1.0));
+
+        // One pulse dents the armored star without destroying it
+        game.handle_input(&InputState::new((0.0, 0.0), true), &mut audio);
+        for _ in 0..20 {
+            game.update_projectiles(&mut audio);
+        }
+        assert_eq!(game.collision_count, 0);
+        assert_eq!(game.big_stars[0].hits_left, 2);
+
+        game.big_stars[1].damage(1);
+        let splitter: BigStar = game.big_stars[1].clone();
+        game.star_destroyed(&splitter, false);
+        assert_eq!(game.big_star_shard_count, 3);
+        assert_eq!(
+            game.big_stars
+                .iter()
+                .filter(|s| s.kind == StarKind::Shard)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_ramming_a_splitter_is_one_collision() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.big_stars
+            .push(BigStar::new(StarKind::Splitter, 0.0, 0.0, 2.0));
+        for _ in 0..3 {
+            game.check_collisions(&mut audio, 0.0, 0.0);
+        }
+        assert_eq!(game.collision_count, 1);
+        assert_eq!(game.big_star_shard_count, 0);
+        assert_eq!((game.health.shield, game.health.hull), (1.0, 3));
+    }
+
+    // A boss that's done coming in, hovering above the ship
+    fn boss_in_place(game: &mut GameState) {
+        let mut boss = Boss::new(BossKind::Warden);
+        boss.z = 2.0;
+        game.boss = Some(boss);
+    }
+
+    #[test]
+    fn test_shots_wear_the_boss_down_until_it_falls() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        boss_in_place(&mut game);
+        let full: u32 = game.boss.as_ref().unwrap().health();
+
+        // Straight up into its lowest weak point
+        game.handle_input(&InputState::new((0.0, 0.0), true), &mut audio);
+        for _ in 0..20 {
+            game.update_projectiles(&mut audio);
+        }
+        assert_eq!(game.boss.as_ref().unwrap().health(), full - 1);
+        assert_eq!(game.scoring.score, 0);
+
+        // Knocking out every weak point scores each of them and then the boss
+        for i in 0..4 {
+            game.boss.as_mut().unwrap().shielded = 0.0;
+            game.hit_boss(i, u8::MAX, 400.0, 250.0, &mut audio);
+        }
+        assert!(game.boss.is_none());
+        assert_eq!(count(&audio.events, SoundEvent::BossPhase), 2);
+        assert_eq!(count(&audio.events, SoundEvent::BossDefeated), 1);
+        assert!(game.scoring.score > 4 * WEAK_POINT_WORTH as usize);
+    }
+
+    #[test]
+    fn test_boss_beams_burn_unless_shielded() {
+        for shield in [false, true] {
+            let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+            let mut audio = RecordingAudio::default();
+            boss_in_place(&mut game);
+            game.boss.as_mut().unwrap().beams.push(Beam::new(true));
+            if shield {
+                game.boosts.activate(PowerUpKind::Shield);
+            }
+
+            // The beam sweeps across the ship once
+            for _ in 0..DEFAULT_TICK_RATE * 3 {
+                game.update_boss(&mut audio);
+            }
+            // Two points of damage: the ship's own shield takes it all
+            let burns: usize = if shield { 0 } else { 1 };
+            assert_eq!(game.health.shield, if shield { 2.0 } else { 0.0 });
+            assert_eq!(count(&audio.events, SoundEvent::ShieldHit), burns);
+            assert_eq!(game.missed_count, 0);
+        }
+    }
+
+    #[test]
+    fn test_projectiles_expire() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.handle_input(&InputState::new((0.0, 0.0), true), &mut audio);
+
+        // Pulses live a second, and leave the top of the field before that
+        for _ in 0..DEFAULT_TICK_RATE {
+            game.update_projectiles(&mut audio);
+        }
+        assert!(game.projectiles.is_empty());
+    }
+
+    #[test]
+    fn test_holding_fire_overheats() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        let fire = InputState::new((0.0, 0.0), true);
+
+        for _ in 0..DEFAULT_TICK_RATE * 5 {
+            game.step(&fire, &mut audio);
+        }
+
+        assert!(game.energy.overheated);
+        assert_eq!(count(&audio.events, SoundEvent::LowEnergy), 1);
+        assert_eq!(count(&audio.events, SoundEvent::Overheat), 1);
+        // Nothing fires while the guns are locked
+        let shots: usize = count(&audio.events, SoundEvent::Fire);
+        game.step(&fire, &mut audio);
+        game.step(&fire, &mut audio);
+        assert_eq!(count(&audio.events, SoundEvent::Fire), shots);
+    }
+
+    #[test]
+    fn test_flying_into_power_ups_collects_them() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // Two right on the ship, one well off to the side
+        for (x, kind) in [
+            (0.0, PowerUpKind::ExtraLife),
+            (0.0, PowerUpKind::Shield),
+            (0.5, PowerUpKind::Magnet),
+        ] {
+            game.power_ups.push(PowerUp {
+                x,
+                y: 0.0,
+                z: 1.0,
+                kind,
+            });
+        }
+
+        game.update_power_ups(&mut audio);
+
+        assert_eq!(game.health.lives, 4);
+        assert!(game.boosts.is_active(PowerUpKind::Shield));
+        assert!(!game.boosts.is_active(PowerUpKind::Magnet));
+        assert_eq!(game.power_ups.len(), 1);
+        assert_eq!(count(&audio.events, SoundEvent::PowerUp), 2);
+    }
+
+    #[test]
+    fn test_shield_catches_stars_around_the_ship() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // 12px right of the ship: outside the hitbox, inside the shield
+        let star = || BigStar::new(StarKind::Normal, 0.03, 0.0, 1.0);
+
+        game.big_stars.push(star());
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.collision_count, 0);
+
+        game.boosts.activate(PowerUpKind::Shield);
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.collision_count, 1);
+        assert!(game.big_stars.is_empty());
+    }
+
+    #[test]
+    fn test_dangerous_stars_hurt_on_contact() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.missed_count = 2;
+
+        // Armored does two damage, which the full shield takes
+        game.big_stars
+            .push(BigStar::new(StarKind::Armored, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!((game.health.shield, game.health.hull), (0.0, 3));
+        assert_eq!(count(&audio.events, SoundEvent::ShieldHit), 1);
+
+        // With the shield down a homer gets through to the hull
+        game.big_stars
+            .push(BigStar::new(StarKind::Homing, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.health.hull, 2);
+        assert_eq!(count(&audio.events, SoundEvent::HullHit), 1);
+        // Neither was a catch, so no misses were taken back
+        assert_eq!(game.missed_count, 2);
+
+        // A harmless one still is
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.missed_count, 1);
+        assert_eq!(game.health.hull, 2);
+
+        // The shield boost makes them all harmless
+        game.health.invulnerable = 0.0;
+        game.boosts.activate(PowerUpKind::Shield);
+        game.big_stars
+            .push(BigStar::new(StarKind::Streaker, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.health.hull, 2);
+        assert_eq!(game.collision_count, 4);
+    }
+
+    #[test]
+    fn test_ramming_dangerous_stars_earns_nothing() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.missed_count = 2;
+
+        // The ship's own shield takes it, but there's nothing in it
+        game.big_stars
+            .push(BigStar::new(StarKind::Armored, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.health.shield, 0.0);
+
+        // Nor with the shield boost up, however many it rams
+        game.boosts.activate(PowerUpKind::Shield);
+        for _ in 0..50 {
+            game.big_stars
+                .push(BigStar::new(StarKind::Armored, 0.0, 0.0, 2.0));
+            game.check_collisions(&mut audio, 0.0, 0.0);
+        }
+        assert_eq!(game.collision_count, 51);
+        assert_eq!(game.scoring.score, 0);
+        assert!(game.power_ups.is_empty() && game.floating_texts.is_empty());
+        assert_eq!(game.missed_count, 2);
+
+        // Catching a harmless one still pays
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert!(game.scoring.score > 0);
+        assert_eq!(game.missed_count, 1);
+    }
+
+    #[test]
+    fn test_losing_the_hull_costs_a_life_then_the_game() {
+        let settings = Settings {
+            lives: 2,
+            hull: 1,
+            shield: 0,
+            ..with_stars(10)
+        };
+        let mut game = GameState::new(&settings, 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // 100px right of the middle
+        game.ship.x += 100.0;
+        let star = || BigStar::new(StarKind::Streaker, 0.25, 0.0, 1.0);
+
+        game.big_stars.push(star());
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.health.lives, 1);
+        assert_eq!(count(&audio.events, SoundEvent::ShipLost), 1);
+        assert!(!game.is_game_over());
+        // The new ship comes in at the middle, blinking
+        assert_eq!(game.ship.x, (WIDTH / 2) as f32);
+        assert!(game.health.invulnerable > 0.0);
+
+        game.health.invulnerable = 0.0;
+        game.big_stars
+            .push(BigStar::new(StarKind::Streaker, 0.0, 0.0, 1.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.health.lives, 0);
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn test_time_attack_ends_on_the_clock_not_on_misses() {
+        // Tough enough to sit out the whole two minutes
+        let settings = Settings {
+            lives: MAX_LIVES,
+            hull: 20,
+            shield: 20,
+            ..with_stars(10)
+        };
+        let mut game = GameState::new(&settings, 1, DEFAULT_TICK_RATE, GameMode::TimeAttack);
+        let mut audio = RecordingAudio::default();
+        // No levels, straight into a thicker stream
+        assert!(game.campaign.is_endless());
+        assert_eq!(game.time_left(), Some(TIME_ATTACK_SECONDS));
+
+        game.missed_count = game.settings.max_escaped + 5;
+        assert!(!game.is_game_over());
+        while game.game_over_frame.is_none() {
+            game.step(&InputState::default(), &mut audio);
+        }
+        assert_eq!(game.time_left(), Some(0.0));
+        assert!((game.elapsed_seconds - TIME_ATTACK_SECONDS).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_zen_never_ends_or_hurts() {
+        let settings: Settings = with_stars(10);
+        let mut game = GameState::new(&settings, 1, DEFAULT_TICK_RATE, GameMode::Zen);
+        let mut audio = RecordingAudio::default();
+        assert_eq!(game.time_left(), None);
+        // No levels and no bosses, just the stream
+        assert!(game.campaign.is_endless());
+
+        game.big_stars
+            .push(BigStar::new(StarKind::Armored, 0.0, 0.0, 2.0));
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        assert_eq!(game.health, Health::new(&settings));
+        assert_eq!(count(&audio.events, SoundEvent::ShieldHit), 0);
+
+        game.missed_count = game.settings.max_escaped * 10;
+        for _ in 0..3000 {
+            game.step(&InputState::default(), &mut audio);
+        }
+        assert!(!game.is_game_over());
+        assert_eq!(game.game_over_frame, None);
+    }
+
+    #[test]
+    fn test_daily_is_the_same_game_whatever_the_settings() {
+        let settings = Settings {
+            start_level: 3,
+            lives: 9,
+            max_escaped: 500,
+            spawn_rate: 0.1,
+            ..with_stars(10)
+        };
+        let game = GameState::new(&settings, 20251018, DEFAULT_TICK_RATE, GameMode::Daily);
+        assert_eq!(game.campaign.banner()[0], "LEVEL 1");
+        assert_eq!(game.settings, with_stars(10));
+        assert_eq!(game.health.lives, Settings::default().lives);
+        assert_eq!(game.replay.settings, game.settings);
+        assert_eq!(game.replay.mode, GameMode::Daily);
+        assert_eq!(game.replay.seed, 20251018);
+    }
+
+    #[test]
+    fn test_magnet_pulls_stars_towards_the_ship() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        game.boosts.activate(PowerUpKind::Magnet);
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.5, -0.5, 2.0));
+
+        let distance = |game: &GameState| -> f32 {
+            let (px, py) = game.big_stars[0].project(WIDTH, HEIGHT);
+            distance_squared(px, py, game.ship.x, game.ship.y).sqrt()
+        };
+        let before: f32 = distance(&game);
+        game.update_big_stars();
+        assert!(distance(&game) < before * 0.99);
+    }
+
+    #[test]
+    fn test_hits_score_by_depth_and_show_points() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // Right on the ship, at depth 2
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.0, 0.0, 2.0));
+
+        game.check_collisions(&mut audio, 0.0, 0.0);
+
+        assert_eq!(game.scoring.score, 20);
+        assert_eq!(game.floating_texts.len(), 1);
+        assert_eq!(game.floating_texts[0].text, "+20");
+        assert_eq!(
+            (game.floating_texts[0].x, game.floating_texts[0].y),
+            (400.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn test_near_miss_scores_once() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        // 15px right of the ship and close to the camera: past the hitbox, inside the near miss
+        game.big_stars
+            .push(BigStar::new(StarKind::Normal, 0.015, 0.0, 0.4));
+
+        game.check_collisions(&mut audio, 0.0, 0.0);
+        game.check_collisions(&mut audio, 0.0, 0.0);
+
+        assert_eq!(game.collision_count, 0);
+        assert_eq!(game.scoring.score, 5);
+        assert_eq!(game.scoring.combo, 0);
+        assert_eq!(game.floating_texts.len(), 1);
+    }
+
+    #[test]
+    fn test_fire_respects_cooldown() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        let fire = InputState::new((0.0, 0.0), true);
+
+        for _ in 0..3 {
+            game.step(&fire, &mut audio);
+        }
+
+        assert_eq!(count(&audio.events, SoundEvent::Fire), 1);
+    }
+
+    #[test]
+    fn test_ship_stays_inside_screen() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        let down_right = InputState::new((1.0, 1.0), false);
+
+        for _ in 0..1000 {
+            game.handle_input(&down_right, &mut audio);
+        }
+
+        assert_eq!(game.ship.x, WIDTH as f32 - 1.0);
+        assert_eq!(game.ship.y, HEIGHT as f32 - 1.0);
+    }
+
+    #[test]
+    fn test_tick_rate_does_not_change_game_speed() {
+        let mut audio = RecordingAudio::default();
+        let right = InputState::new((1.0, 0.0), false);
+
+        let mut positions: Vec<f32> = vec![];
+        for tick_rate in [30, 60, 240] {
+            let mut game = GameState::new(&with_stars(10), 1, tick_rate, GameMode::Endless);
+            // One second of holding right
+            for _ in 0..tick_rate {
+                game.step(&right, &mut audio);
+            }
+            assert!((game.elapsed_seconds - 1.0).abs() < 1e-4);
+            positions.push(game.ship.x);
+        }
+
+        // Integration error grows with the tick length but stays small
+        for x in &positions {
+            assert!((x - positions[2]).abs() < 8.0, "{positions:?}");
+        }
+    }
+
+    #[test]
+    fn test_ship_accelerates_up_to_max_speed() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        let right = InputState::new((1.0, 0.0), false);
+
+        game.handle_input(&right, &mut audio);
+        let first_tick: f32 = game.ship.vx;
+        assert!(first_tick > 0.0 && first_tick < SHIP_MAX_SPEED / 4.0);
+
+        for _ in 0..60 {
+            game.handle_input(&right, &mut audio);
+        }
+        assert!((game.ship.vx - SHIP_MAX_SPEED).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_diagonal_is_not_faster() {
+        let mut audio = RecordingAudio::default();
+        let mut speeds: Vec<f32> = vec![];
+        for axis in [(1.0, 0.0), (1.0, 1.0)] {
+            let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+            for _ in 0..10 {
+                game.handle_input(&InputState::new(axis, false), &mut audio);
+            }
+            speeds.push((game.ship.vx * game.ship.vx + game.ship.vy * game.ship.vy).sqrt());
+        }
+        assert!((speeds[0] - speeds[1]).abs() < 1.0, "{speeds:?}");
+    }
+
+    #[test]
+    fn test_ship_drifts_to_a_stop() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.ship.vx = SHIP_MAX_SPEED;
+
+        // Keeps coasting after the stick is released...
+        let start: f32 = game.ship.x;
+        game.handle_input(&InputState::default(), &mut audio);
+        assert!(game.ship.x > start);
+
+        // ...until drag brings it to rest
+        for _ in 0..300 {
+            game.handle_input(&InputState::default(), &mut audio);
+        }
+        assert!(game.ship.vx.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_particles_move_once_per_tick() {
+        let mut game = GameState::new(&with_stars(10), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        game.particles.push(Particle {
+            x: 100.0,
+            y: 100.0,
+            vx: 60.0,
+            vy: 0.0,
+            life: 1.0,
+            initial_life: 1.0,
+        });
+
+        game.step(&InputState::default(), &mut audio);
+
+        assert!((game.particles[0].x - 101.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_render_scales_to_canvas() {
+        let mut game = GameState::new(&with_stars(0), 1, DEFAULT_TICK_RATE, GameMode::Endless);
+        game.ship = Ship::new(200.0, 150.0);
+
+        // Twice the play field: the ship lands at twice the position, drawn twice as big
+        let mut small = Canvas::new(WIDTH, HEIGHT);
+        let mut big = Canvas::new(WIDTH * 2, HEIGHT * 2);
+        game.render(&mut small, 1.0);
+        game.render(&mut big, 1.0);
+
+        let lit = |canvas: &Canvas| canvas.buffer.iter().filter(|p| **p != 0).count();
+        assert_eq!(lit(&big), lit(&small) * 4);
+        assert_eq!(big.get_pixel(400, 300 - 8), Some(0xFFFFFF));
+        assert_eq!(small.get_pixel(200, 150 - 4), Some(0xFFFFFF));
+    }
+
+    #[test]
+    fn test_render_is_repeatable() {
+        let mut game = GameState::new(&with_stars(100), 5, DEFAULT_TICK_RATE, GameMode::Endless);
+        let mut audio = RecordingAudio::default();
+        let fire = InputState::new((0.0, 0.0), true);
+        for _ in 0..600 {
+            game.step(&fire, &mut audio);
+        }
+        let stars_before: Vec<f32> = game.stars.iter().map(|s| s.z).collect();
+
+        let mut first = Canvas::new(WIDTH, HEIGHT);
+        let mut second = Canvas::new(WIDTH, HEIGHT);
+        game.render(&mut first, 0.5);
+        game.render(&mut second, 0.5);
+
+        assert!(first.buffer.iter().any(|p| *p != 0));
+        assert_eq!(first, second);
+        let stars_after: Vec<f32> = game.stars.iter().map(|s| s.z).collect();
+        assert_eq!(stars_before, stars_after);
     }
 }
@@ -0,0 +1,332 @@
+// Golden-image tests for the software renderer. Each test draws a fixed scene
+// and compares it with tests/golden/<name>.png.
+//
+// After an intentional change to the rendering, regenerate the references with
+//   EAGLE_BLESS=1 cargo test golden
+// and look at the new images before committing them. When a comparison fails the
+// actual frame and a diff (mismatches in red over a dimmed copy of the frame) are
+// written to target/golden/.
+use crate::bindings::Action;
+use crate::boss::{Beam, Boss, BossKind};
+use crate::canvas::Canvas;
+use crate::draw_text::draw_text;
+use crate::game_mode::GameMode;
+use crate::game_state::{DEFAULT_TICK_RATE, GameState};
+use crate::highscores::HighScore;
+use crate::input::InputState;
+use crate::particles::Particle;
+use crate::png;
+use crate::powerups::{POWER_UP_KINDS, PowerUp, PowerUpKind};
+use crate::screen::{Menus, Screen};
+use crate::settings::Settings;
+use crate::simple_random::SimpleRng;
+use crate::sound::NullAudio;
+use crate::space_objects::{BigStar, Ship, StarKind};
+use minifb::Key;
+use std::fs;
+use std::path::PathBuf;
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+
+// Largest per-channel difference still counted as a match, to absorb float rounding
+const TOLERANCE: u32 = 2;
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden")
+}
+
+fn channels_match(a: u32, b: u32) -> bool {
+    [16, 8, 0]
+        .iter()
+        .all(|shift| ((a >> shift) & 0xFF).abs_diff((b >> shift) & 0xFF) <= TOLERANCE)
+}
+
+fn assert_golden(name: &str, actual: &Canvas) {
+    let path: PathBuf = golden_dir().join(format!("{name}.png"));
+
+    if std::env::var("EAGLE_BLESS").is_ok_and(|v| v == "1") {
+        fs::create_dir_all(golden_dir()).unwrap();
+        png::save(actual, &path).unwrap();
+        return;
+    }
+
+    let expected: Canvas = png::decode::load(&path).unwrap_or_else(|message| {
+        panic!("{message}\nRun with EAGLE_BLESS=1 to create the reference image")
+    });
+
+    let mut diff: Canvas = Canvas::new(actual.width, actual.height);
+    let mut mismatched: usize = 0;
+    if expected.width == actual.width && expected.height == actual.height {
+        for (i, (a, e)) in actual.buffer.iter().zip(expected.buffer.iter()).enumerate() {
+            if channels_match(*a, *e) {
+                diff.buffer[i] = (a >> 2) & 0x3F3F3F;
+            } else {
+                diff.buffer[i] = 0xFF0000;
+                mismatched += 1;
+            }
+        }
+        if mismatched == 0 {
+            return;
+        }
+    }
+
+    fs::create_dir_all(output_dir()).unwrap();
+    let actual_path: PathBuf = output_dir().join(format!("{name}.actual.png"));
+    let diff_path: PathBuf = output_dir().join(format!("{name}.diff.png"));
+    png::save(actual, &actual_path).unwrap();
+    png::save(&diff, &diff_path).unwrap();
+
+    panic!(
+        "{name}: rendering differs from {} ({mismatched} pixels off, expected {}x{}, got {}x{})\nSee {} and {}",
+        path.display(),
+        expected.width,
+        expected.height,
+        actual.width,
+        actual.height,
+        actual_path.display(),
+        diff_path.display()
+    );
+}
+
+fn settings(width: usize, height: usize, stars: usize) -> Settings {
+    Settings {
+        width,
+        height,
+        stars,
+        ..Settings::default()
+    }
+}
+
+// A game with nothing in it but what the test adds
+fn empty_game() -> GameState {
+    let mut game = GameState::new(
+        &settings(WIDTH, HEIGHT, 0),
+        1,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    // Park the ship in a corner so it doesn't overlap the scene
+    game.ship = Ship::new(5.0, 5.0);
+    game
+}
+
+#[test]
+fn golden_starfield() {
+    let mut game = GameState::new(
+        &settings(WIDTH, HEIGHT, 400),
+        42,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    let mut audio = NullAudio;
+    for _ in 0..30 {
+        game.step(&InputState::default(), &mut audio);
+    }
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT);
+    game.render(&mut canvas, 1.0);
+    assert_golden("starfield", &canvas);
+}
+
+#[test]
+fn golden_big_stars() {
+    let mut game = empty_game();
+    for (x, y, z) in [(0.0, 0.0, 3.0), (-0.5, 0.3, 1.5), (0.4, -0.4, 0.8)] {
+        game.big_stars.push(BigStar::new(StarKind::Normal, x, y, z));
+    }
+    // Close enough to the last star for its proximity box
+    game.ship = Ship::new(240.0, 60.0);
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT);
+    game.render(&mut canvas, 1.0);
+    assert_golden("big_stars", &canvas);
+}
+
+#[test]
+fn golden_fail_text() {
+    let mut canvas = Canvas::new(500, 132);
+    draw_text(&mut canvas, 10, 10, "FAIL!", 0x225599, 16);
+    assert_golden("fail_text", &canvas);
+}
+
+#[test]
+fn golden_particle_burst() {
+    let mut game = empty_game();
+    let mut rng = SimpleRng::new(3);
+    for _ in 0..40 {
+        let angle: f32 = rng.random_range_f32(0.0..std::f32::consts::TAU);
+        let distance: f32 = rng.random_range_f32(5.0..60.0);
+        game.particles.push(Particle {
+            x: 160.0 + angle.cos() * distance,
+            y: 120.0 + angle.sin() * distance,
+            vx: 0.0,
+            vy: 0.0,
+            life: rng.random_range_f32(0.2..1.0),
+            initial_life: 1.0,
+        });
+    }
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT);
+    game.render(&mut canvas, 1.0);
+    assert_golden("particle_burst", &canvas);
+}
+
+#[test]
+fn golden_power_ups() {
+    let mut game = empty_game();
+    for (i, kind) in POWER_UP_KINDS.into_iter().enumerate() {
+        game.power_ups.push(PowerUp {
+            x: -0.8 + 0.4 * i as f32,
+            y: -0.5,
+            z: 1.0 + 0.25 * i as f32,
+            kind,
+        });
+    }
+    game.ship = Ship::new(160.0, 170.0);
+    game.boosts.activate(PowerUpKind::Shield);
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT);
+    game.render(&mut canvas, 1.0);
+    assert_golden("power_ups", &canvas);
+}
+
+#[test]
+fn golden_redemption_flash() {
+    let mut game = empty_game();
+    game.ship = Ship::new(160.0, 120.0);
+    game.redemption_flash_timer = 0.15;
+
+    let mut canvas = Canvas::new(WIDTH, HEIGHT);
+    game.render(&mut canvas, 1.0);
+    assert_golden("redemption_flash", &canvas);
+}
+
+#[test]
+fn golden_game_over_screen() {
+    let mut game = GameState::new(
+        &settings(800, 600, 200),
+        7,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    game.scoring.score = 42;
+    game.missed_count = 10;
+    game.elapsed_seconds = 93.5;
+
+    let mut canvas = Canvas::new(800, 600);
+    game.render(&mut canvas, 1.0);
+    Screen::GameOver.draw_overlay(&mut canvas, &game, &Menus::default());
+    assert_golden("game_over_screen", &canvas);
+}
+
+#[test]
+fn golden_high_scores_screen() {
+    let game = GameState::new(
+        &settings(800, 600, 200),
+        7,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    let mut menus = Menus::default();
+    for (name, score, seconds) in [("EGL", 42, 93), ("BOB", 17, 40), ("AMY", 17, 55)] {
+        menus.high_scores.insert(HighScore {
+            name: name.to_string(),
+            score,
+            seconds,
+            missed: 10,
+            date: 1_760_745_600,
+            seed: 1234567890,
+            mode: GameMode::Endless,
+        });
+    }
+    menus.highlight = Some(1);
+
+    let mut canvas = Canvas::new(800, 600);
+    game.render(&mut canvas, 1.0);
+    Screen::HighScores.draw_overlay(&mut canvas, &game, &menus);
+    assert_golden("high_scores_screen", &canvas);
+}
+
+#[test]
+fn golden_controls_screen() {
+    let game = GameState::new(
+        &settings(800, 600, 200),
+        7,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    let mut menus = Menus::default();
+    menus.bindings.bind(Action::Fire, Key::J);
+    menus.controls.selected = 5;
+    menus.controls.capturing = true;
+
+    let mut canvas = Canvas::new(800, 600);
+    game.render(&mut canvas, 1.0);
+    Screen::Controls.draw_overlay(&mut canvas, &game, &menus);
+    assert_golden("controls_screen", &canvas);
+}
+
+#[test]
+fn golden_level_banner() {
+    let game = GameState::new(
+        &settings(800, 600, 200),
+        7,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+
+    let mut canvas = Canvas::new(800, 600);
+    game.render(&mut canvas, 1.0);
+    Screen::Playing.draw_overlay(&mut canvas, &game, &Menus::default());
+    assert_golden("level_banner", &canvas);
+}
+
+#[test]
+fn golden_boss() {
+    let mut game = GameState::new(
+        &settings(800, 600, 200),
+        7,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    let mut boss = Boss::new(BossKind::Hive);
+    boss.z = 2.0;
+    boss.weak_points[0].health = 0;
+    boss.weak_points[1].health = 3;
+    boss.phase = 1;
+    // One beam still warning, one burning partway through its sweep
+    let mut burning = Beam::new(true);
+    burning.warning = 0.0;
+    burning.angle = -0.3;
+    boss.beams = vec![Beam::new(false), burning];
+    game.boss = Some(boss);
+
+    let mut canvas = Canvas::new(800, 600);
+    game.render(&mut canvas, 1.0);
+    crate::draw_hud(&mut canvas, &game);
+    assert_golden("boss", &canvas);
+}
+
+#[test]
+fn golden_title_screen() {
+    let game = GameState::new(
+        &settings(800, 600, 200),
+        7,
+        DEFAULT_TICK_RATE,
+        GameMode::Endless,
+    );
+    let menus = Menus {
+        mode: GameMode::TimeAttack,
+        ..Menus::default()
+    };
+
+    let mut canvas = Canvas::new(800, 600);
+    game.render(&mut canvas, 1.0);
+    Screen::Title.draw_overlay(&mut canvas, &game, &menus);
+    assert_golden("title_screen", &canvas);
+}
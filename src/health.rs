@@ -0,0 +1,183 @@
+use crate::settings::Settings;
+
+// Seconds after a hit before the shield starts coming back, and shield points it regains a second
+const SHIELD_REGEN_DELAY: f32 = 3.0;
+const SHIELD_REGEN: f32 = 0.5;
+// Seconds the ship can't be hurt after a hit that got through the shield, and after a respawn
+const HIT_GRACE: f32 = 0.5;
+const RESPAWN_GRACE: f32 = 2.5;
+// Most lives that can be banked with extra lives
+pub const MAX_LIVES: usize = 9;
+
+// What a hit did to the ship
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hurt {
+    // Still blinking from the last hit or respawn, so nothing
+    Ignored,
+    // The shield took all of it
+    Absorbed,
+    // Some got through to the hull
+    Damaged,
+    // The hull gave out and a life went with it; the ship comes back with a fresh hull and shield
+    LifeLost,
+    // The hull gave out on the last life
+    Destroyed,
+}
+
+// The ship's shield, hull and spare lives. Damage wears down the shield first, which comes back on
+// its own after a while without hits; the hull only comes back with a new life.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Health {
+    pub shield: f32,
+    pub max_shield: f32,
+    pub hull: usize,
+    pub max_hull: usize,
+    // Ships left, counting the one in play
+    pub lives: usize,
+    // Seconds left of not being hurt
+    pub invulnerable: f32,
+    // Seconds until the shield starts regenerating
+    regen_delay: f32,
+}
+
+impl Health {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            shield: settings.shield as f32,
+            max_shield: settings.shield as f32,
+            hull: settings.hull,
+            max_hull: settings.hull,
+            lives: settings.lives,
+            invulnerable: 0.0,
+            regen_delay: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+        if self.regen_delay > 0.0 {
+            self.regen_delay -= dt;
+        } else {
+            self.shield = (self.shield + SHIELD_REGEN * dt).min(self.max_shield);
+        }
+    }
+
+    // Takes `damage` points, each off a whole point of shield if there is one, else off the hull
+    pub fn hurt(&mut self, damage: usize) -> Hurt {
+        if self.invulnerable > 0.0 || self.lives == 0 {
+            return Hurt::Ignored;
+        }
+        self.regen_delay = SHIELD_REGEN_DELAY;
+
+        let mut got_through: bool = false;
+        for _ in 0..damage {
+            if self.shield >= 1.0 {
+                self.shield -= 1.0;
+            } else {
+                self.hull = self.hull.saturating_sub(1);
+                got_through = true;
+            }
+        }
+        if !got_through {
+            return Hurt::Absorbed;
+        }
+        if self.hull > 0 {
+            self.invulnerable = HIT_GRACE;
+            return Hurt::Damaged;
+        }
+
+        self.lives -= 1;
+        if self.lives == 0 {
+            return Hurt::Destroyed;
+        }
+        self.hull = self.max_hull;
+        self.shield = self.max_shield;
+        self.regen_delay = 0.0;
+        self.invulnerable = RESPAWN_GRACE;
+        Hurt::LifeLost
+    }
+
+    pub fn add_life(&mut self) {
+        self.lives = (self.lives + 1).min(MAX_LIVES);
+    }
+
+    // Whether to draw the ship this tick; it blinks while it can't be hurt
+    pub fn is_visible(&self) -> bool {
+        self.invulnerable <= 0.0 || ((self.invulnerable * 10.0) as usize).is_multiple_of(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    fn health() -> Health {
+        Health::new(&Settings {
+            shield: 2,
+            hull: 3,
+            lives: 2,
+            ..Settings::default()
+        })
+    }
+
+    fn wait(health: &mut Health, seconds: f32) {
+        for _ in 0..(seconds / DT).round() as usize {
+            health.tick(DT);
+        }
+    }
+
+    #[test]
+    fn test_shield_takes_hits_before_the_hull() {
+        let mut health = health();
+        assert_eq!(health.hurt(1), Hurt::Absorbed);
+        assert_eq!(health.hurt(2), Hurt::Damaged);
+        assert_eq!((health.shield, health.hull), (0.0, 2));
+
+        // Too soon to be hurt again
+        assert_eq!(health.hurt(1), Hurt::Ignored);
+        assert!(health.invulnerable > 0.0);
+        wait(&mut health, HIT_GRACE + DT);
+        assert_eq!(health.hurt(1), Hurt::Damaged);
+        assert_eq!(health.hull, 1);
+    }
+
+    #[test]
+    fn test_shield_regenerates_after_a_quiet_spell() {
+        let mut health = health();
+        health.hurt(2);
+        wait(&mut health, SHIELD_REGEN_DELAY - 0.5);
+        assert_eq!(health.shield, 0.0);
+        wait(&mut health, 0.5 + 2.0 / SHIELD_REGEN + 0.1);
+        assert_eq!(health.shield, 2.0);
+        // The hull doesn't come back
+        health.hurt(3);
+        wait(&mut health, 10.0);
+        assert_eq!(health.hull, 2);
+    }
+
+    #[test]
+    fn test_losing_the_hull_costs_a_life() {
+        let mut health = health();
+        assert_eq!(health.hurt(5), Hurt::LifeLost);
+        assert_eq!(health.lives, 1);
+        assert_eq!((health.shield, health.hull), (2.0, 3));
+        assert_eq!(health.hurt(5), Hurt::Ignored);
+
+        wait(&mut health, RESPAWN_GRACE + DT);
+        assert!(health.is_visible());
+        assert_eq!(health.hurt(5), Hurt::Destroyed);
+        assert_eq!(health.lives, 0);
+        assert_eq!(health.hurt(5), Hurt::Ignored);
+    }
+
+    #[test]
+    fn test_extra_lives_are_capped() {
+        let mut health = health();
+        for _ in 0..20 {
+            health.add_life();
+        }
+        assert_eq!(health.lives, MAX_LIVES);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::game_mode::GameMode;
+use crate::paths;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// File layout: a header line, then one tab separated entry per line, best first:
+//   eagle-highscores <version>
+//   <name> <score> <seconds survived> <missed> <unix time> <seed> <mode (since v3)>
+// then, if there is one, a LEGACY_HEADER line and the legacy table in the same layout.
+// Lines that don't parse are dropped, so a damaged file loses entries rather than the table.
+// Entries without a mode are from before there were modes, so endless. Version 1 scores counted
+// stars hit rather than points, so a version 1 table becomes the legacy table instead of being
+// ranked against points.
+const HEADER: &str = "eagle-highscores";
+const VERSION: u32 = 3;
+const LEGACY_HEADER: &str = "stars hit";
+
+pub const MAX_ENTRIES: usize = 10;
+pub const NAME_LEN: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HighScore {
+    pub name: String,
+    pub score: usize,
+    pub seconds: u64,
+    pub missed: usize,
+    // Unix time in seconds when the game ended
+    pub date: u64,
+    pub seed: u64,
+    pub mode: GameMode,
+}
+
+// Every mode's table in one list, each kept to its best MAX_ENTRIES
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    // Games from before scores were points, kept as they were and never ranked against them
+    pub legacy: Vec<HighScore>,
+}
+
+impl HighScore {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.name,
+            self.score,
+            self.seconds,
+            self.missed,
+            self.date,
+            self.seed,
+            self.mode.name()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<HighScore> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (name, score, seconds, missed, date, seed, mode) = match fields[..] {
+            [name, score, seconds, missed, date, seed] => {
+                (name, score, seconds, missed, date, seed, GameMode::Endless)
+            }
+            [name, score, seconds, missed, date, seed, mode] => (
+                name,
+                score,
+                seconds,
+                missed,
+                date,
+                seed,
+                GameMode::from_name(mode)?,
+            ),
+            _ => return None,
+        };
+        if !is_valid_name(name) {
+            return None;
+        }
+        Some(HighScore {
+            name: name.to_string(),
+            score: score.parse().ok()?,
+            seconds: seconds.parse().ok()?,
+            missed: missed.parse().ok()?,
+            date: date.parse().ok()?,
+            seed: seed.parse().ok()?,
+            mode,
+        })
+    }
+
+    // The date part of `date` as YYYY-MM-DD (UTC)
+    pub fn date_string(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86_400) as i64);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+impl HighScores {
+    // One mode's table, best first
+    pub fn table(&self, mode: GameMode) -> Vec<&HighScore> {
+        self.entries.iter().filter(|e| e.mode == mode).collect()
+    }
+
+    // Would a game with this score make it onto the mode's table?
+    pub fn qualifies(&self, mode: GameMode, score: usize) -> bool {
+        let table: Vec<&HighScore> = self.table(mode);
+        score > 0
+            && (table.len() < MAX_ENTRIES || table.last().is_some_and(|last| score > last.score))
+    }
+
+    // Returns the new entry's position in its mode's table, or None if it didn't make the cut
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let mode: GameMode = entry.mode;
+        if !self.qualifies(mode, entry.score) {
+            return None;
+        }
+        // Ties go to whoever got there first; after that the longer survival wins
+        let index: usize = self
+            .entries
+            .iter()
+            .position(|e| {
+                entry.score > e.score || (entry.score == e.score && entry.seconds > e.seconds)
+            })
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+
+        // Whoever that pushed off the bottom of the mode's table
+        let dropped: Option<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.mode == mode)
+            .nth(MAX_ENTRIES)
+            .map(|(i, _)| i);
+        if let Some(i) = dropped {
+            self.entries.remove(i);
+        }
+        Some(
+            self.entries[..index]
+                .iter()
+                .filter(|e| e.mode == mode)
+                .count(),
+        )
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text: String = format!("{HEADER} {VERSION}\n");
+        for entry in &self.entries {
+            text.push_str(&entry.to_line());
+            text.push('\n');
+        }
+        if !self.legacy.is_empty() {
+            text.push_str(LEGACY_HEADER);
+            text.push('\n');
+            for entry in &self.legacy {
+                text.push_str(&entry.to_line());
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<HighScores, String> {
+        let mut lines = text.lines();
+        let version: u32 = lines
+            .next()
+            .and_then(|header| header.strip_prefix(HEADER))
+            .and_then(|version| version.trim().parse().ok())
+            .ok_or("Not an eagle high-score file")?;
+        if version == 0 || version > VERSION {
+            return Err(format!("Unsupported high-score file version {version}"));
+        }
+
+        let mut scores: HighScores = HighScores::default();
+        let mut legacy: bool = version == 1;
+        for line in lines {
+            if line == LEGACY_HEADER {
+                legacy = true;
+            } else if let Some(entry) = HighScore::from_line(line) {
+                if legacy {
+                    scores.legacy.push(entry);
+                } else {
+                    scores.insert(entry);
+                }
+            }
+        }
+        scores.legacy.truncate(MAX_ENTRIES);
+        Ok(scores)
+    }
+
+    // A missing file is just an empty table
+    pub fn load(path: &Path) -> Result<HighScores, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => HighScores::from_text(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HighScores::default()),
+            Err(e) => Err(format!(
+                "Could not read high scores {}: {e}",
+                path.display()
+            )),
+        }
+    }
+
+    // Written next to the real file and renamed over it, so a crash never leaves half a table
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+        }
+        let temp: PathBuf = path.with_extension("tmp");
+        fs::write(&temp, self.to_text())
+            .and_then(|()| fs::rename(&temp, path))
+            .map_err(|e| format!("Could not write high scores {}: {e}", path.display()))
+    }
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= NAME_LEN && name.chars().all(|c| c.is_ascii_uppercase())
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+// Where the table lives: the platform's per-user data directory, in an "eagle" folder
+pub fn default_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("eagle").join("highscores.txt"))
+}
+
+// Days since 1970-01-01 to a (year, month, day) date, from Howard Hinnant's date algorithms
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z: i64 = days + 719_468;
+    let era: i64 = z.div_euclid(146_097);
+    let doe: i64 = z - era * 146_097;
+    let yoe: i64 = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy: i64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp: i64 = (5 * doy + 2) / 153;
+    let day: u32 = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month: u32 = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year: i64 = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: usize, seconds: u64) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            seconds,
+            missed: 10,
+            date: 1_760_745_600,
+            seed: 99,
+            mode: GameMode::Endless,
+        }
+    }
+
+    #[test]
+    fn test_insert_keeps_best_ten_in_order() {
+        let mut scores = HighScores::default();
+        for score in 1..=12 {
+            scores.insert(entry("AAA", score, 60));
+        }
+        assert_eq!(scores.entries.len(), MAX_ENTRIES);
+        assert_eq!(scores.entries[0].score, 12);
+        assert_eq!(scores.entries[9].score, 3);
+
+        assert!(!scores.qualifies(GameMode::Endless, 3));
+        assert_eq!(scores.insert(entry("BBB", 2, 60)), None);
+        assert_eq!(scores.insert(entry("CCC", 7, 61)), Some(5));
+        assert_eq!(scores.entries[6].score, 7);
+    }
+
+    #[test]
+    fn test_zero_never_qualifies() {
+        assert!(!HighScores::default().qualifies(GameMode::Endless, 0));
+    }
+
+    #[test]
+    fn test_modes_keep_separate_tables() {
+        let mut scores = HighScores::default();
+        for score in 1..=10 {
+            scores.insert(entry("AAA", score * 10, 60));
+        }
+        assert!(!scores.qualifies(GameMode::Endless, 5));
+        let daily = |score: usize| HighScore {
+            mode: GameMode::Daily,
+            ..entry("DAY", score, 60)
+        };
+        assert!(scores.qualifies(GameMode::Daily, 5));
+        assert_eq!(scores.insert(daily(5)), Some(0));
+        assert_eq!(scores.insert(daily(55)), Some(0));
+        assert_eq!(scores.insert(daily(7)), Some(1));
+
+        // A full daily table doesn't push endless entries out
+        for _ in 0..10 {
+            scores.insert(daily(1000));
+        }
+        assert_eq!(scores.table(GameMode::Endless).len(), MAX_ENTRIES);
+        assert_eq!(scores.table(GameMode::Daily).len(), MAX_ENTRIES);
+        assert_eq!(scores.table(GameMode::Endless)[9].score, 10);
+        assert!(scores.table(GameMode::TimeAttack).is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut scores = HighScores::default();
+        scores.insert(entry("EGL", 42, 93));
+        scores.insert(entry("BOB", 17, 40));
+        scores.insert(HighScore {
+            mode: GameMode::TimeAttack,
+            ..entry("AMY", 900, 120)
+        });
+        assert_eq!(HighScores::from_text(&scores.to_text()), Ok(scores));
+    }
+
+    #[test]
+    fn test_skips_damaged_lines() {
+        let text = "eagle-highscores 2\n\
+                    EGL\t42\t93\t10\t1760745600\t7\n\
+                    garbage\n\
+                    BOB\tlots\t40\t10\t1760745600\t7\n\
+                    toolong\t5\t40\t10\t1760745600\t7\n\
+                    AMY\t9\t20\t10\t1760745600\n\
+                    BEN\t9\t20\t10\t1760745600\t7\tbogus\n\
+                    CAT\t8\t20\t10\t1760745600\t7\tzen";
+        let scores = HighScores::from_text(text).unwrap();
+        assert_eq!(scores.entries.len(), 2);
+        assert_eq!(scores.entries[0].name, "EGL");
+        // Lines from before modes are endless
+        assert_eq!(scores.entries[0].mode, GameMode::Endless);
+        assert_eq!(scores.entries[1].mode, GameMode::Zen);
+    }
+
+    #[test]
+    fn test_rejects_unknown_files() {
+        assert!(HighScores::from_text("").is_err());
+        assert!(HighScores::from_text("some other file\n").is_err());
+        assert!(HighScores::from_text("eagle-highscores 4\n").is_err());
+    }
+
+    #[test]
+    fn test_keeps_hit_count_tables_apart() {
+        let text = "eagle-highscores 1\n\
+                    EGL\t42\t93\t10\t1760745600\t7\n\
+                    BOB\t17\t40\t10\t1760745600\t7\n";
+        let mut scores = HighScores::from_text(text).unwrap();
+        assert!(scores.entries.is_empty());
+        assert_eq!(scores.legacy.len(), 2);
+        assert_eq!(scores.legacy[0].name, "EGL");
+
+        // Points are ranked on their own, and the old table survives being saved again
+        assert!(scores.qualifies(GameMode::Endless, 1));
+        assert_eq!(scores.insert(entry("AMY", 5, 20)), Some(0));
+        let text = scores.to_text();
+        assert!(text.starts_with("eagle-highscores 3\n"));
+        assert_eq!(HighScores::from_text(&text), Ok(scores));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("eagle-test-{}", std::process::id()))
+            .join("highscores.txt");
+        assert_eq!(HighScores::load(&path), Ok(HighScores::default()));
+
+        let mut scores = HighScores::default();
+        scores.insert(entry("EGL", 42, 93));
+        scores.save(&path).unwrap();
+        assert_eq!(HighScores::load(&path), Ok(scores));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_date_string() {
+        assert_eq!(entry("EGL", 1, 1).date_string(), "2025-10-18");
+        let epoch = HighScore {
+            date: 0,
+            ..entry("EGL", 1, 1)
+        };
+        assert_eq!(epoch.date_string(), "1970-01-01");
+    }
+}
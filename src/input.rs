@@ -0,0 +1,148 @@
+use crate::bindings::{Action, Bindings};
+use minifb::Window;
+
+// Axis values are stored as whole steps out of AXIS_MAX so replays stay exact
+pub const AXIS_MAX: i8 = 127;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InputState {
+    // Steering, negative is left/up
+    pub x: i8,
+    pub y: i8,
+    pub fire: bool,
+    // Held state of the weapon buttons; the game acts when they go down
+    pub next_weapon: bool,
+    pub bomb: bool,
+}
+
+// Anything that can steer the ship: the keyboard today, a gamepad stick later
+pub trait AxisSource {
+    // Desired direction, each component in -1.0..=1.0
+    fn axis(&self) -> (f32, f32);
+}
+
+// Movement keys as an axis: full deflection while held, opposite keys cancel out
+pub struct KeyAxis<'a> {
+    pub window: &'a Window,
+    pub bindings: &'a Bindings,
+}
+
+impl AxisSource for KeyAxis<'_> {
+    fn axis(&self) -> (f32, f32) {
+        let held = |action: Action| -> f32 {
+            if self.bindings.is_down(self.window, action) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+        (
+            held(Action::MoveRight) - held(Action::MoveLeft),
+            held(Action::MoveDown) - held(Action::MoveUp),
+        )
+    }
+}
+
+impl InputState {
+    pub fn new(axis: (f32, f32), fire: bool) -> Self {
+        let quantize = |value: f32| (value.clamp(-1.0, 1.0) * AXIS_MAX as f32).round() as i8;
+        Self {
+            x: quantize(axis.0),
+            y: quantize(axis.1),
+            fire,
+            next_weapon: false,
+            bomb: false,
+        }
+    }
+
+    pub fn from_window(window: &Window, bindings: &Bindings) -> Self {
+        Self {
+            next_weapon: bindings.is_down(window, Action::NextWeapon),
+            bomb: bindings.is_down(window, Action::Bomb),
+            ..Self::from_source(
+                &KeyAxis { window, bindings },
+                bindings.is_down(window, Action::Fire),
+            )
+        }
+    }
+
+    pub fn from_source(source: &dyn AxisSource, fire: bool) -> Self {
+        Self::new(source.axis(), fire)
+    }
+
+    // Steering direction, never longer than 1 so diagonals aren't faster than straight lines
+    pub fn axis(&self) -> (f32, f32) {
+        let x: f32 = self.x as f32 / AXIS_MAX as f32;
+        let y: f32 = self.y as f32 / AXIS_MAX as f32;
+        let length: f32 = (x * x + y * y).sqrt();
+        if length > 1.0 {
+            (x / length, y / length)
+        } else {
+            (x, y)
+        }
+    }
+
+    // Packed form used by replay files; the first byte holds fire, next weapon and bomb bits
+    pub fn to_bytes(self) -> [u8; 3] {
+        let buttons: u8 = self.fire as u8 | (self.next_weapon as u8) << 1 | (self.bomb as u8) << 2;
+        [buttons, self.x as u8, self.y as u8]
+    }
+
+    pub fn from_bytes(bytes: [u8; 3]) -> Self {
+        Self {
+            fire: bytes[0] & 1 != 0,
+            next_weapon: bytes[0] & 2 != 0,
+            bomb: bytes[0] & 4 != 0,
+            x: bytes[1] as i8,
+            y: bytes[2] as i8,
+        }
+    }
+
+    // Replays before version 3 stored one bit per key: left, right, up, down, fire
+    pub fn from_key_bits(bits: u8) -> Self {
+        let held = |bit: u8| ((bits >> bit) & 1) as i8;
+        Self {
+            x: (held(1) - held(0)) * AXIS_MAX,
+            y: (held(3) - held(2)) * AXIS_MAX,
+            fire: held(4) != 0,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagonals_are_normalized() {
+        let (x, y) = InputState::new((1.0, -1.0), false).axis();
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() < 1e-3);
+        assert!(x > 0.0 && y < 0.0);
+
+        // Partial deflection stays partial
+        let (x, y) = InputState::new((0.5, 0.0), false).axis();
+        assert!((x - 0.5).abs() < 0.01);
+        assert_eq!(y, 0.0);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let input = InputState::new((-0.25, 1.0), true);
+        assert_eq!(InputState::from_bytes(input.to_bytes()), input);
+        let input = InputState {
+            bomb: true,
+            ..InputState::new((0.0, -1.0), false)
+        };
+        assert_eq!(InputState::from_bytes(input.to_bytes()), input);
+    }
+
+    #[test]
+    fn test_key_bits() {
+        // right + up + fire
+        let input = InputState::from_key_bits(0b10110);
+        assert_eq!(input, InputState::new((1.0, -1.0), true));
+        // left and right cancel
+        assert_eq!(InputState::from_key_bits(0b00011).x, 0);
+    }
+}
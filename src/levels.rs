@@ -0,0 +1,766 @@
+use crate::boss::{BOSS_KINDS, BossKind};
+use crate::game_mode::GameMode;
+use crate::settings::Settings;
+use crate::simple_random::SimpleRng;
+use crate::space_objects::{BigStar, StarKind};
+
+// The levels, in order. They're built in so a replay always meets the same ones; the format is
+// described in README.md and at the top of each file.
+pub const BUILTIN_LEVELS: [&str; 4] = [
+    include_str!("../levels/01-first-light.txt"),
+    include_str!("../levels/02-crosswinds.txt"),
+    include_str!("../levels/03-heavy-metal.txt"),
+    include_str!("../levels/04-storm-front.txt"),
+];
+
+// Seconds of calm before each wave, and before and after each level
+const WAVE_BREAK: f32 = 2.0;
+const LEVEL_BREAK: f32 = 3.0;
+// Seconds the warning is up before a level's boss comes in
+const BOSS_WARNING: f32 = 2.5;
+// Seconds between bosses once the levels are done
+const ENDLESS_BOSS_INTERVAL: f64 = 90.0;
+
+// Spacing between stars in a formation, and the size of rings and scatters, in the big stars'
+// x/y/z units
+const FORMATION_GAP: f32 = 0.4;
+const RING_RADIUS: f32 = 0.8;
+const SCATTER: f32 = 1.0;
+// How far away formations are when a spawn line doesn't say
+const DEFAULT_DEPTH: f32 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Formation {
+    // Side by side across the field
+    Line,
+    // One above the other
+    Column,
+    // Evenly round a circle
+    Ring,
+    // A leader with the rest fanning out behind it
+    Vee,
+    // One behind the other, so they arrive in turn
+    Queue,
+    // Anywhere near the spot
+    Scatter,
+}
+
+impl Formation {
+    fn from_name(name: &str) -> Option<Formation> {
+        match name {
+            "line" => Some(Formation::Line),
+            "column" => Some(Formation::Column),
+            "ring" => Some(Formation::Ring),
+            "vee" => Some(Formation::Vee),
+            "queue" => Some(Formation::Queue),
+            "scatter" => Some(Formation::Scatter),
+            _ => None,
+        }
+    }
+
+    // Where each of `count` stars goes for a formation centred on (x, y, z)
+    pub fn positions(
+        self,
+        count: usize,
+        (x, y, z): (f32, f32, f32),
+        rng: &mut SimpleRng,
+    ) -> Vec<(f32, f32, f32)> {
+        let middle: f32 = (count as f32 - 1.0) / 2.0;
+        (0..count)
+            .map(|i| {
+                let offset: f32 = (i as f32 - middle) * FORMATION_GAP;
+                match self {
+                    Formation::Line => (x + offset, y, z),
+                    Formation::Column => (x, y + offset, z),
+                    Formation::Ring => {
+                        let angle: f32 = i as f32 * std::f32::consts::TAU / count as f32;
+                        (
+                            x + angle.cos() * RING_RADIUS,
+                            y + angle.sin() * RING_RADIUS,
+                            z,
+                        )
+                    }
+                    Formation::Vee => {
+                        let rank: f32 = i.div_ceil(2) as f32;
+                        let side: f32 = if i % 2 == 1 { -1.0 } else { 1.0 };
+                        (x + side * rank * FORMATION_GAP, y, z + rank * FORMATION_GAP)
+                    }
+                    Formation::Queue => (x, y, z + i as f32 * FORMATION_GAP),
+                    Formation::Scatter => (
+                        x + rng.random_range_f32(-SCATTER..SCATTER),
+                        y + rng.random_range_f32(-SCATTER..SCATTER),
+                        z + rng.random_range_f32(0.0..SCATTER),
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+// One line of a wave: `count` stars in a formation, some seconds after the wave starts
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spawn {
+    pub at: f32,
+    // None for the usual random mix
+    pub kind: Option<StarKind>,
+    pub formation: Formation,
+    pub count: usize,
+    pub position: (f32, f32, f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wave {
+    // In the order they go
+    pub spawns: Vec<Spawn>,
+}
+
+// What finishes a level. Whatever it is, the level only ends once the field is clear.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goal {
+    // Get through every wave
+    Clear,
+    // Last this many seconds, going round the waves again if need be
+    Survive(f32),
+    // Score this many points in the level, going round the waves again if need be
+    Score(usize),
+}
+
+impl Goal {
+    fn describe(self) -> String {
+        match self {
+            Goal::Clear => "CLEAR EVERY WAVE".to_string(),
+            Goal::Survive(seconds) => format!("SURVIVE {} SECONDS", seconds.round() as usize),
+            Goal::Score(points) => format!("SCORE {points} POINTS"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name: String,
+    pub goal: Goal,
+    // Scales how fast its stars come in
+    pub speed: f32,
+    pub waves: Vec<Wave>,
+    // Comes in once the goal is met; the level isn't over until it's beaten
+    pub boss: Option<BossKind>,
+}
+
+impl Level {
+    // Same rules as the settings file: every bad line is reported, one per line of the error
+    pub fn from_text(text: &str) -> Result<Level, String> {
+        let mut level: Level = Level {
+            name: String::new(),
+            goal: Goal::Clear,
+            speed: 1.0,
+            waves: Vec::new(),
+            boss: None,
+        };
+        let mut errors: Vec<String> = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[wave]" {
+                level.waves.push(Wave { spawns: Vec::new() });
+                continue;
+            }
+            let result: Result<(), String> = match line.split_once('=') {
+                Some((key, value)) => level.set(key.trim(), value.trim()),
+                None => Err("expected 'key = value' or '[wave]'".to_string()),
+            };
+            if let Err(message) = result {
+                errors.push(format!("line {}: {message}", number + 1));
+            }
+        }
+
+        if level.waves.is_empty() {
+            errors.push("no [wave] sections".to_string());
+        }
+        for (i, wave) in level.waves.iter_mut().enumerate() {
+            if wave.spawns.is_empty() {
+                errors.push(format!("wave {} has no spawns", i + 1));
+            }
+            wave.spawns.sort_by(|a, b| a.at.total_cmp(&b.at));
+        }
+
+        if errors.is_empty() {
+            Ok(level)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    // Keys before the first [wave] describe the level; inside a wave there are only spawns
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match (self.waves.last_mut(), key) {
+            (Some(wave), "spawn") => wave.spawns.push(parse_spawn(value)?),
+            (Some(_), _) => return Err(format!("unknown wave key '{key}'")),
+            (None, "name") => self.name = value.to_uppercase(),
+            (None, "goal") => self.goal = parse_goal(value)?,
+            (None, "boss") => {
+                self.boss =
+                    Some(BossKind::from_name(value).ok_or(format!("unknown boss '{value}'"))?)
+            }
+            (None, "speed") => {
+                self.speed = value
+                    .parse()
+                    .ok()
+                    .filter(|speed| (0.1..=10.0).contains(speed))
+                    .ok_or(format!(
+                        "speed expects a number from 0.1 to 10, got '{value}'"
+                    ))?
+            }
+            (None, _) => return Err(format!("unknown level key '{key}'")),
+        }
+        Ok(())
+    }
+}
+
+// `clear`, `survive <seconds>` or `score <points>`
+fn parse_goal(value: &str) -> Result<Goal, String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    let goal: Option<Goal> = match words[..] {
+        ["clear"] => Some(Goal::Clear),
+        ["survive", seconds] => seconds
+            .parse()
+            .ok()
+            .filter(|s: &f32| *s > 0.0)
+            .map(Goal::Survive),
+        ["score", points] => points.parse().ok().filter(|p| *p > 0).map(Goal::Score),
+        _ => None,
+    };
+    goal.ok_or(format!(
+        "goal expects 'clear', 'survive <seconds>' or 'score <points>', got '{value}'"
+    ))
+}
+
+// `<seconds> <kind> <formation> <count> [x y z]`
+fn parse_spawn(value: &str) -> Result<Spawn, String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    let usage =
+        || format!("spawn expects '<seconds> <kind> <formation> <count> [x y z]', got '{value}'");
+    if words.len() != 4 && words.len() != 7 {
+        return Err(usage());
+    }
+
+    let at: f32 = words[0]
+        .parse()
+        .ok()
+        .filter(|at: &f32| *at >= 0.0)
+        .ok_or_else(usage)?;
+    let kind: Option<StarKind> = match words[1] {
+        "any" => None,
+        name => Some(StarKind::from_name(name).ok_or(format!("unknown star kind '{name}'"))?),
+    };
+    let formation: Formation =
+        Formation::from_name(words[2]).ok_or(format!("unknown formation '{}'", words[2]))?;
+    let count: usize = words[3]
+        .parse()
+        .ok()
+        .filter(|n| (1..=50).contains(n))
+        .ok_or(format!("count expects 1 to 50, got '{}'", words[3]))?;
+    let position: (f32, f32, f32) = if words.len() == 7 {
+        let numbers: Vec<f32> = words[4..]
+            .iter()
+            .map(|word| word.parse().map_err(|_| usage()))
+            .collect::<Result<_, _>>()?;
+        if numbers[2] <= 0.1 {
+            return Err(format!("z has to be more than 0.1, got '{}'", words[6]));
+        }
+        (numbers[0], numbers[1], numbers[2])
+    } else {
+        (0.0, 0.0, DEFAULT_DEPTH)
+    };
+
+    Ok(Spawn {
+        at,
+        kind,
+        formation,
+        count,
+        position,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Phase {
+    // Counting down to the next wave with the banner up
+    Break(f32),
+    // Seconds into the current wave, and how many of its spawns have gone
+    Wave {
+        time: f32,
+        next: usize,
+    },
+    // The level's goal is met and its boss is on the way
+    BossWarning(f32),
+    // Waiting for the boss and whatever it sent to be gone
+    BossFight,
+    // The level's goal is met; counting down to the next one
+    Complete(f32),
+    // Past the last level: a steady stream that keeps getting busier, `seconds` after it began,
+    // with a boss every so often
+    Endless {
+        seconds: f64,
+        spawned: u32,
+        next_boss: f64,
+    },
+}
+
+// What happened on one tick of the campaign
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub spawned: Vec<BigStar>,
+    // A wave just ended with the field clear
+    pub wave_cleared: bool,
+    // A boss to bring in
+    pub boss: Option<BossKind>,
+}
+
+// Where the game is in the levels, and what to spawn next
+#[derive(Clone, Debug, PartialEq)]
+pub struct Campaign {
+    levels: Vec<Level>,
+    // Index of the current level, and of the wave in it
+    pub level: usize,
+    pub wave: usize,
+    // Waves started in this level, counting repeats
+    pub waves_played: usize,
+    level_time: f32,
+    // Score when the level started, for score goals
+    level_score: usize,
+    // Bosses met in endless play, to take turns between the kinds
+    endless_bosses: usize,
+    // Tunes the endless stream
+    mode: GameMode,
+    phase: Phase,
+}
+
+impl Campaign {
+    // Starts at level index `start`; past the last level means going straight to endless
+    pub fn new(levels: Vec<Level>, start: usize) -> Self {
+        let mut campaign: Campaign = Campaign {
+            levels,
+            level: 0,
+            wave: 0,
+            waves_played: 0,
+            level_time: 0.0,
+            level_score: 0,
+            endless_bosses: 0,
+            mode: GameMode::Endless,
+            phase: Phase::Break(0.0),
+        };
+        campaign.start_level(start, 0);
+        campaign
+    }
+
+    // The built-in levels are checked by the tests, so they always parse
+    pub fn builtin(start: usize) -> Self {
+        let levels: Vec<Level> = BUILTIN_LEVELS
+            .iter()
+            .map(|text| Level::from_text(text).expect("built-in level"))
+            .collect();
+        Campaign::new(levels, start)
+    }
+
+    // Modes that skip the levels go straight to the stream; the daily challenge always starts at
+    // the beginning so it's the same game for everyone
+    pub fn for_mode(mode: GameMode, start: usize) -> Self {
+        let mut campaign: Campaign = match mode {
+            GameMode::Daily => Campaign::builtin(0),
+            _ if mode.plays_levels() => Campaign::builtin(start),
+            _ => Campaign::new(Vec::new(), 0),
+        };
+        campaign.mode = mode;
+        campaign
+    }
+
+    pub fn is_endless(&self) -> bool {
+        matches!(self.phase, Phase::Endless { .. })
+    }
+
+    // Scales how fast big stars come in
+    pub fn speed(&self) -> f32 {
+        self.levels.get(self.level).map_or(1.0, |level| level.speed)
+    }
+
+    fn start_level(&mut self, index: usize, score: usize) {
+        self.level = index;
+        self.wave = 0;
+        self.waves_played = 0;
+        self.level_time = 0.0;
+        self.level_score = score;
+        self.phase = if index < self.levels.len() {
+            Phase::Break(LEVEL_BREAK)
+        } else {
+            Phase::Endless {
+                seconds: 0.0,
+                spawned: 0,
+                next_boss: ENDLESS_BOSS_INTERVAL,
+            }
+        };
+    }
+
+    fn goal_met(&self, score: usize) -> bool {
+        match self.levels[self.level].goal {
+            // Only the last wave finishes these
+            Goal::Clear => false,
+            Goal::Survive(seconds) => self.level_time >= seconds,
+            Goal::Score(points) => score - self.level_score >= points,
+        }
+    }
+
+    // One tick of `dt` seconds. `field_clear` says no big stars are left in play, and `score`
+    // is the game's score so far.
+    pub fn tick(
+        &mut self,
+        dt: f32,
+        field_clear: bool,
+        score: usize,
+        settings: &Settings,
+        rng: &mut SimpleRng,
+    ) -> Progress {
+        let mut progress: Progress = Progress::default();
+        self.level_time += dt;
+
+        self.phase = match self.phase.clone() {
+            Phase::Break(left) if left > dt => Phase::Break(left - dt),
+            Phase::Break(_) => {
+                self.waves_played += 1;
+                Phase::Wave { time: 0.0, next: 0 }
+            }
+            Phase::Wave { time, mut next } => {
+                let time: f32 = time + dt;
+                let goal_met: bool = self.goal_met(score);
+                let level: &Level = &self.levels[self.level];
+                let spawns: &[Spawn] = &level.waves[self.wave].spawns;
+                // Once the goal is met nothing more comes; the level ends when the field clears
+                if goal_met {
+                    next = spawns.len();
+                }
+                while next < spawns.len() && spawns[next].at <= time {
+                    let spawn: &Spawn = &spawns[next];
+                    for (x, y, z) in spawn.formation.positions(spawn.count, spawn.position, rng) {
+                        let kind: StarKind = spawn.kind.unwrap_or_else(|| StarKind::random(rng));
+                        progress.spawned.push(BigStar::launch(kind, x, y, z, rng));
+                    }
+                    next += 1;
+                }
+
+                if next < spawns.len() || !field_clear || !progress.spawned.is_empty() {
+                    Phase::Wave { time, next }
+                } else {
+                    progress.wave_cleared = true;
+                    let last: bool = self.wave + 1 == level.waves.len();
+                    if goal_met || (last && level.goal == Goal::Clear) {
+                        match level.boss {
+                            Some(_) => Phase::BossWarning(BOSS_WARNING),
+                            None => Phase::Complete(LEVEL_BREAK),
+                        }
+                    } else {
+                        self.wave = (self.wave + 1) % level.waves.len();
+                        Phase::Break(WAVE_BREAK)
+                    }
+                }
+            }
+            Phase::BossWarning(left) if left > dt => Phase::BossWarning(left - dt),
+            Phase::BossWarning(_) => {
+                progress.boss = self.levels[self.level].boss;
+                Phase::BossFight
+            }
+            // The boss is in play from the tick it was sent, and counts towards a clear field
+            Phase::BossFight if field_clear => Phase::Complete(LEVEL_BREAK),
+            Phase::BossFight => Phase::BossFight,
+            Phase::Complete(left) if left > dt => Phase::Complete(left - dt),
+            Phase::Complete(_) => {
+                self.start_level(self.level + 1, score);
+                self.phase.clone()
+            }
+            Phase::Endless {
+                seconds,
+                mut spawned,
+                mut next_boss,
+            } => {
+                // Stars per second start at spawn_rate and grow by spawn_ramp every second, both
+                // scaled for the mode
+                let stars_per_second: f64 = settings.spawn_rate * self.mode.spawn_scale()
+                    + seconds * settings.spawn_ramp * self.mode.ramp_scale();
+                let expected_total: u32 = (seconds * stars_per_second).floor() as u32;
+                while spawned < expected_total {
+                    progress.spawned.push(BigStar::spawn(rng));
+                    spawned += 1;
+                }
+                if seconds >= next_boss && self.mode.has_bosses() {
+                    progress.boss = Some(BOSS_KINDS[self.endless_bosses % BOSS_KINDS.len()]);
+                    self.endless_bosses += 1;
+                    next_boss += ENDLESS_BOSS_INTERVAL;
+                }
+                Phase::Endless {
+                    seconds: seconds + dt as f64,
+                    spawned,
+                    next_boss,
+                }
+            }
+        };
+        progress
+    }
+
+    // Lines for the intermission banner, biggest first; empty while a wave is on
+    pub fn banner(&self) -> Vec<String> {
+        match self.phase {
+            Phase::Break(_) if self.waves_played == 0 => {
+                let level: &Level = &self.levels[self.level];
+                [
+                    format!("LEVEL {}", self.level + 1),
+                    level.name.clone(),
+                    level.goal.describe(),
+                ]
+                .into_iter()
+                .filter(|line| !line.is_empty())
+                .collect()
+            }
+            Phase::Break(_) => vec![format!("WAVE {}", self.waves_played + 1)],
+            Phase::BossWarning(_) => vec![
+                "WARNING".to_string(),
+                format!(
+                    "{} APPROACHING",
+                    self.levels[self.level].boss.map_or("BOSS", BossKind::label)
+                ),
+            ],
+            Phase::Complete(_) if self.level + 1 < self.levels.len() => vec![
+                format!("LEVEL {} COMPLETE", self.level + 1),
+                format!("ON TO LEVEL {}", self.level + 2),
+            ],
+            Phase::Complete(_) => vec![
+                "ALL LEVELS COMPLETE".to_string(),
+                "ENDLESS FROM HERE".to_string(),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    // Short progress line for the HUD
+    pub fn status(&self) -> String {
+        let Some(level) = self.levels.get(self.level) else {
+            return "ENDLESS".to_string();
+        };
+        let wave: usize = self.waves_played.max(1);
+        if let (Some(boss), Phase::BossWarning(_) | Phase::BossFight) = (level.boss, &self.phase) {
+            return format!("LEVEL {}  {}", self.level + 1, boss.label());
+        }
+        match level.goal {
+            Goal::Clear => format!(
+                "LEVEL {}  WAVE {}/{}",
+                self.level + 1,
+                wave,
+                level.waves.len()
+            ),
+            _ => format!("LEVEL {}  WAVE {}", self.level + 1, wave),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    const TWO_WAVES: &str = "name = Test\n\
+        [wave]\n\
+        spawn = 0.5 normal line 3 0 0 3   # three across\n\
+        [wave]\n\
+        spawn = 0 armored queue 1\n\
+        spawn = 1 any ring 4 0.2 0.1 2.5\n";
+
+    // Ticks until something spawns or `seconds` pass, returning what spawned and when
+    fn run(
+        campaign: &mut Campaign,
+        seconds: f32,
+        field_clear: bool,
+        rng: &mut SimpleRng,
+    ) -> (Vec<BigStar>, f32) {
+        let mut time: f32 = 0.0;
+        while time < seconds {
+            time += DT;
+            let progress = campaign.tick(DT, field_clear, 0, &Settings::default(), rng);
+            if !progress.spawned.is_empty() {
+                return (progress.spawned, time);
+            }
+        }
+        (Vec::new(), time)
+    }
+
+    #[test]
+    fn test_builtin_levels_parse() {
+        for text in BUILTIN_LEVELS {
+            let level = Level::from_text(text).unwrap();
+            assert!(!level.name.is_empty());
+        }
+        assert!(!Campaign::builtin(0).is_endless());
+        assert!(Campaign::builtin(BUILTIN_LEVELS.len()).is_endless());
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        let text = "goal = forever\nspawn = 0 normal line 1\n[wave]\n\
+            spawn = 0 comet line 1\nspawn = 0 normal blob 1\nspawn = 0 normal line 0\n\
+            spawn = 0 normal\nspeed = 2\n[wave]\n";
+        let errors = Level::from_text(text).unwrap_err();
+        let lines: Vec<&str> = errors.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].starts_with("line 1: goal expects"));
+        assert_eq!(lines[1], "line 2: unknown level key 'spawn'");
+        assert_eq!(lines[2], "line 4: unknown star kind 'comet'");
+        assert_eq!(lines[3], "line 5: unknown formation 'blob'");
+        assert!(lines[4].starts_with("line 6: count expects"));
+        assert!(lines[5].starts_with("line 7: spawn expects"));
+        assert_eq!(lines[6], "line 8: unknown wave key 'speed'");
+        // None of the first wave's spawns were any good either
+        assert_eq!(lines[7], "wave 1 has no spawns");
+        assert_eq!(lines[8], "wave 2 has no spawns");
+    }
+
+    #[test]
+    fn test_formations() {
+        let mut rng = SimpleRng::new(1);
+        let line = Formation::Line.positions(3, (0.0, 0.5, 3.0), &mut rng);
+        assert_eq!(
+            line,
+            vec![(-0.4, 0.5, 3.0), (0.0, 0.5, 3.0), (0.4, 0.5, 3.0)]
+        );
+
+        // The leader is nearest, with a pair a step behind it on either side
+        let vee = Formation::Vee.positions(3, (0.0, 0.0, 2.0), &mut rng);
+        assert_eq!(vee[0], (0.0, 0.0, 2.0));
+        assert_eq!((vee[1].0, vee[2].0), (-0.4, 0.4));
+        assert!(vee[1].2 > 2.0 && vee[1].2 == vee[2].2);
+
+        let ring = Formation::Ring.positions(4, (0.0, 0.0, 3.0), &mut rng);
+        for (x, y, _) in ring {
+            assert!(((x * x + y * y).sqrt() - RING_RADIUS).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_waves_play_in_order_then_endless() {
+        let level = Level::from_text(TWO_WAVES).unwrap();
+        let mut campaign = Campaign::new(vec![level], 0);
+        let mut rng = SimpleRng::new(5);
+        assert_eq!(
+            campaign.banner(),
+            vec!["LEVEL 1", "TEST", "CLEAR EVERY WAVE"]
+        );
+
+        // The opening break, then half a second into the first wave
+        let (spawned, time) = run(&mut campaign, 10.0, false, &mut rng);
+        assert!((time - LEVEL_BREAK - 0.5).abs() < 2.0 * DT, "{time}");
+        assert_eq!(spawned.len(), 3);
+        assert!(spawned.iter().all(|star| star.kind == StarKind::Normal));
+        assert_eq!(campaign.status(), "LEVEL 1  WAVE 1/2");
+
+        // Nothing more until the field is clear
+        assert!(run(&mut campaign, 5.0, false, &mut rng).0.is_empty());
+        assert!(campaign.banner().is_empty());
+        let progress = campaign.tick(DT, true, 0, &Settings::default(), &mut rng);
+        assert!(progress.wave_cleared);
+        assert_eq!(campaign.banner(), vec!["WAVE 2"]);
+
+        let (spawned, _) = run(&mut campaign, 10.0, true, &mut rng);
+        assert_eq!(spawned.len(), 1);
+        assert_eq!(spawned[0].kind, StarKind::Armored);
+        assert_eq!(run(&mut campaign, 10.0, true, &mut rng).0.len(), 4);
+        campaign.tick(DT, true, 0, &Settings::default(), &mut rng);
+        assert_eq!(campaign.banner()[0], "ALL LEVELS COMPLETE");
+
+        // The endless stream takes over after the level's break
+        let (spawned, time) = run(&mut campaign, 10.0, true, &mut rng);
+        assert!(campaign.is_endless());
+        assert_eq!(spawned.len(), 1);
+        assert!(time > LEVEL_BREAK);
+        assert_eq!(campaign.status(), "ENDLESS");
+    }
+
+    #[test]
+    fn test_survive_goal_repeats_waves_until_time_is_up() {
+        let text = TWO_WAVES.replace("name = Test", "goal = survive 20\nspeed = 1.5");
+        let level = Level::from_text(&text).unwrap();
+        assert_eq!(level.speed, 1.5);
+        let mut campaign = Campaign::new(vec![level.clone(), level], 0);
+        assert_eq!(campaign.speed(), 1.5);
+        let mut rng = SimpleRng::new(5);
+
+        let mut spawned: usize = 0;
+        let mut time: f32 = 0.0;
+        while campaign.banner().first().map(String::as_str) != Some("LEVEL 1 COMPLETE") {
+            time += DT;
+            spawned += campaign
+                .tick(DT, true, 0, &Settings::default(), &mut rng)
+                .spawned
+                .len();
+        }
+        // Both waves, then round to the first again, until 20 seconds were up
+        assert!(campaign.waves_played > 2);
+        assert!(spawned > 8);
+        assert!((20.0..23.0).contains(&time), "{time}");
+        assert_eq!(campaign.banner()[1], "ON TO LEVEL 2");
+    }
+
+    #[test]
+    fn test_boss_comes_after_the_goal_and_holds_the_level() {
+        let text = TWO_WAVES.replace("name = Test", "name = Test\nboss = hive");
+        let level = Level::from_text(&text).unwrap();
+        assert_eq!(level.boss, Some(BossKind::Hive));
+        assert!(Level::from_text("boss = kraken\n[wave]\nspawn = 0 normal line 1").is_err());
+        let mut campaign = Campaign::new(vec![level], 0);
+        let mut rng = SimpleRng::new(5);
+
+        // Through both waves to the warning
+        let mut boss: Option<BossKind> = None;
+        let mut ticks: usize = 0;
+        while campaign.banner().first().map(String::as_str) != Some("WARNING") {
+            ticks += 1;
+            assert!(ticks < 6000);
+            boss = boss.or(campaign
+                .tick(DT, true, 0, &Settings::default(), &mut rng)
+                .boss);
+        }
+        assert_eq!(boss, None);
+        assert_eq!(campaign.banner()[1], "HIVE APPROACHING");
+        assert_eq!(campaign.status(), "LEVEL 1  HIVE");
+
+        // The boss is sent once the warning's up, and the level waits for it to be beaten
+        let mut time: f32 = 0.0;
+        while boss.is_none() {
+            time += DT;
+            boss = campaign
+                .tick(DT, false, 0, &Settings::default(), &mut rng)
+                .boss;
+        }
+        assert_eq!(boss, Some(BossKind::Hive));
+        assert!((time - BOSS_WARNING).abs() < 2.0 * DT, "{time}");
+        run(&mut campaign, 10.0, false, &mut rng);
+        assert!(campaign.banner().is_empty());
+        assert_eq!(campaign.status(), "LEVEL 1  HIVE");
+        campaign.tick(DT, true, 0, &Settings::default(), &mut rng);
+        assert_eq!(campaign.banner()[0], "ALL LEVELS COMPLETE");
+    }
+
+    #[test]
+    fn test_endless_bosses_take_turns() {
+        let mut campaign = Campaign::new(Vec::new(), 0);
+        let mut rng = SimpleRng::new(5);
+        let mut bosses: Vec<(BossKind, f32)> = Vec::new();
+        let mut time: f32 = 0.0;
+        while bosses.len() < 3 {
+            time += DT;
+            let progress = campaign.tick(DT, true, 0, &Settings::default(), &mut rng);
+            bosses.extend(progress.boss.map(|kind| (kind, time)));
+        }
+        let kinds: Vec<BossKind> = bosses.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, vec![BOSS_KINDS[0], BOSS_KINDS[1], BOSS_KINDS[0]]);
+        let gap: f32 = bosses[1].1 - bosses[0].1;
+        assert!((gap - ENDLESS_BOSS_INTERVAL as f32).abs() < 0.1, "{gap}");
+    }
+}
@@ -1,112 +1,711 @@
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, Window};
 use rodio::OutputStream;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+mod bindings;
+use bindings::Bindings;
+
+mod boss;
+use boss::{Boss, PHASES};
+
+mod canvas;
+use canvas::Canvas;
+
+mod cli;
+use cli::CliOptions;
 
 mod particles;
+mod paths;
+mod powerups;
+mod scoring;
+
+mod display;
+use display::{DisplayMode, open_window};
 
 mod draw_text;
-use draw_text::{draw_number, draw_text};
+use draw_text::{Anchor, anchored, draw_number, draw_text, text_width};
+
+mod game_mode;
+use game_mode::{GameMode, daily_seed};
+
+mod health;
+use health::Health;
+
+mod input;
+use input::InputState;
+
+mod levels;
+
+mod png;
+
+#[cfg(test)]
+mod golden;
 
-mod sound;
-mod utils;
 mod simple_random;
+use simple_random::time_seed;
+mod sound;
+use sound::{AudioSink, NullAudio};
 mod space_objects;
+use space_objects::Ship;
+mod utils;
+mod weapons;
+use weapons::{Energy, MAX_ENERGY};
 
-mod game_state;
-use game_state::GameState;
+mod highscores;
+use highscores::{HighScore, HighScores};
 
-const WIDTH: usize = 800;
-const HEIGHT: usize = 600;
-const NUM_PARTICLES: usize = 40;
-const NUM_STARS: usize = 1000;
-const MAX_ESCAPED: usize = 10;
+mod settings;
+use settings::Settings;
 
-const _RED: u32 = 0xFF0000;
-const GREEN: u32 = 0x00FF00;
-const _BLUE: u32 = 0x0000FF;
+mod screen;
+use screen::{Initials, MenuInput, Menus, Screen, Status, Transition};
+
+mod replay;
+use replay::{Replay, ReplayResult};
+
+mod game_state;
+use game_state::{DEFAULT_TICK_RATE, GameState};
+
+const MAX_FRAME_TIME: f64 = 0.25;
 
 fn main() {
-    let mut window: Window = Window::new(
-        "Starfield + Ship + Particles - ESC to exit",
-        WIDTH,
-        HEIGHT,
-        WindowOptions::default(),
-    )
-    .unwrap();
-
-    window.set_target_fps(60);
-
-    let mut game: GameState = GameState::new(WIDTH, HEIGHT, NUM_STARS);
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let mut buffer: Vec<u32> = vec![0u32; WIDTH * HEIGHT];
-
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        game.update(&window, &mut buffer, WIDTH, HEIGHT, &stream_handle);
-        game.check_and_shake(&stream_handle, WIDTH, HEIGHT, NUM_PARTICLES);
-
-        // Remove hit big stars
-        game.big_stars.retain(|star| !star.hit);
-
-        // Handle keyboard
-        game.handle_input(&window, &stream_handle, WIDTH, HEIGHT, NUM_PARTICLES);
-
-        // --- Update and draw particles ---
-        game.update_particles(&mut buffer, WIDTH, HEIGHT);
-
-        let (shake_offset_x, shake_offset_y) = game.shake_offsets();
-
-        let draw_x: isize = (game.ship_x as f32 + shake_offset_x).round() as isize;
-        let draw_y: isize = (game.ship_y as f32 + shake_offset_y).round() as isize;
-
-        //draw ship
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if (dx == 0 || dy == 0)
-                    && draw_x + dx >= 0
-                    && draw_x + dx < WIDTH as isize
-                    && draw_y + dy >= 0
-                    && draw_y + dy < HEIGHT as isize
-                {
-                    let index = (draw_y + dy) as usize * WIDTH + (draw_x + dx) as usize;
-                    buffer[index] = 0xFFFFFF; // white cross
+    let options: CliOptions = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    let playback: Option<Replay> = options.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|message| {
+            eprintln!("{message}");
+            std::process::exit(1);
+        })
+    });
+    if let Some(replay) = &playback
+        && replay.is_outdated()
+    {
+        eprintln!(
+            "Warning: this replay (version {}) was recorded with an older version of the game and \
+             won't play back the way it was recorded",
+            replay.version
+        );
+    }
+
+    // A replay brings the settings it was recorded with
+    let settings: Settings = match &playback {
+        Some(replay) => replay.settings.clone(),
+        None => load_settings(&options).unwrap_or_else(|message| {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }),
+    };
+
+    if let Some(dir) = &options.dump_frames
+        && let Err(e) = fs::create_dir_all(dir)
+    {
+        eprintln!("Could not create {}: {e}", dir.display());
+        std::process::exit(1);
+    }
+
+    if let Some(replay) = &playback
+        && options.headless
+    {
+        let result: ReplayResult = play_headless(replay, options.dump_frames.as_deref());
+        report_playback(replay, &result);
+        return;
+    }
+
+    let mut display: DisplayMode = options.display;
+    let mut window: Window =
+        open_window(display, settings.width, settings.height).unwrap_or_else(|message| {
+            eprintln!("{message}");
+            std::process::exit(1);
+        });
+
+    let tick_rate: u32 = match &playback {
+        Some(replay) => replay.tick_rate,
+        None => options.tick_rate.unwrap_or(DEFAULT_TICK_RATE),
+    };
+    let mut game: GameState = new_game(
+        &options,
+        &settings,
+        playback.as_ref(),
+        tick_rate,
+        GameMode::default(),
+    );
+    let mut canvas: Canvas = Canvas::new(settings.width, settings.height);
+    // Only used with a pixel scale: the world is drawn here, then blown up into `canvas`
+    let mut world: Canvas = Canvas::new(0, 0);
+
+    // Keep the stream alive for the whole session; fall back to silence without a device
+    let output_stream = OutputStream::try_default().ok();
+    let mut audio: Box<dyn AudioSink> = match &output_stream {
+        Some((_stream, stream_handle)) => Box::new(stream_handle.clone()),
+        None => {
+            println!("No audio device found, running without sound");
+            Box::new(NullAudio)
+        }
+    };
+
+    // Scores are only written back if the existing file could be read, so a damaged or
+    // newer file is never overwritten
+    let mut high_score_path: Option<PathBuf> = highscores::default_path();
+    let high_scores: HighScores = match &high_score_path {
+        Some(path) => HighScores::load(path).unwrap_or_else(|message| {
+            eprintln!("{message}; high scores won't be saved");
+            high_score_path = None;
+            HighScores::default()
+        }),
+        None => HighScores::default(),
+    };
+    let bindings_path: Option<PathBuf> = bindings::default_path();
+    let bindings: Bindings = match &bindings_path {
+        Some(path) => match Bindings::load(path) {
+            Ok((bindings, warnings)) => {
+                for warning in warnings {
+                    eprintln!("{}: {warning}", path.display());
                 }
+                bindings
             }
+            Err(message) => {
+                eprintln!("{message}; using the default keys");
+                Bindings::default()
+            }
+        },
+        None => Bindings::default(),
+    };
+
+    let mut menus: Menus = Menus {
+        high_scores,
+        bindings,
+        ..Menus::default()
+    };
+
+    // Replays go straight into the game, everyone else starts at the title screen
+    let mut screen: Screen = match &playback {
+        Some(_) => {
+            println!("Seed: {}", game.seed);
+            Screen::Playing
         }
+        None => Screen::Title,
+    };
+
+    let tick: f64 = game.dt as f64;
+    let mut accumulator: f64 = 0.0;
+    let mut last_time: Instant = Instant::now();
+    let mut frames_dumped: usize = 0;
 
-        draw_number(
-            &mut buffer,
-            WIDTH,
-            10,
-            10,
-            game.collision_count,
-            0xffffff,
-            4,
-        ); // white color
-        draw_number(&mut buffer, WIDTH, 10, 40, game.missed_count, 0xff0000, 4); // red color
+    while window.is_open() {
+        // A replay with no inputs left has nothing more to play, so treat it like a lost game
+        let replay_finished: bool = playback
+            .as_ref()
+            .is_some_and(|replay| game.replay.frames.len() >= replay.frames.len());
+        let game_over: bool = game.is_game_over() || replay_finished;
 
-        draw_text(&mut buffer, WIDTH, 10, 580, "eagle", 0xFF00FF00, 2);
+        let status: Status = Status {
+            game_over,
+            // Replays watch someone else's game, so they never go on the table
+            high_score: playback.is_none()
+                && game.mode.has_high_scores()
+                && menus.high_scores.qualifies(game.mode, game.scoring.score),
+            capturing_key: menus.controls.capturing,
+        };
+
+        let menu: MenuInput = MenuInput::from_window(&window, &menus.bindings);
+        match screen.next(&menu, &status) {
+            Transition::Stay => match screen {
+                Screen::NameEntry => menus.initials.edit(&menu),
+                Screen::Controls => menus.controls.edit(&menu, &mut menus.bindings),
+                Screen::Title | Screen::HighScores => menus.pick_mode(&menu),
+                _ => {}
+            },
+            Transition::Go(next) => {
+                if screen == Screen::Controls
+                    && let Some(path) = &bindings_path
+                    && let Err(message) = menus.bindings.save(path)
+                {
+                    eprintln!("{message}");
+                }
+                if screen.ends_game(next) {
+                    if game.is_game_over() {
+                        println!(
+                            "{} over on tick {}! (seed {})",
+                            game.mode.label(),
+                            game.replay.frames.len(),
+                            game.seed
+                        );
+                    }
+                    finish_game(&game, &options, playback.as_ref());
+                }
+                // Each entry starts from blank initials, not the last player's
+                if next == Screen::NameEntry {
+                    menus.initials = Initials::default();
+                }
+                menus.highlight = None;
+                screen = next;
+            }
+            Transition::SubmitScore => {
+                menus.highlight = menus.high_scores.insert(HighScore {
+                    name: menus.initials.name(),
+                    score: game.scoring.score,
+                    seconds: game.elapsed_seconds as u64,
+                    missed: game.missed_count,
+                    date: highscores::now(),
+                    seed: game.seed,
+                    mode: game.mode,
+                });
+                if let Some(path) = &high_score_path
+                    && let Err(message) = menus.high_scores.save(path)
+                {
+                    eprintln!("{message}");
+                }
+                screen = Screen::HighScores;
+            }
+            Transition::NewGame => {
+                game = new_game(
+                    &options,
+                    &settings,
+                    playback.as_ref(),
+                    tick_rate,
+                    menus.mode,
+                );
+                // Print the seed so any session can be replayed with --seed
+                println!("Seed: {}", game.seed);
+                accumulator = 0.0;
+                screen = Screen::Playing;
+            }
+            Transition::Quit => {
+                if screen.shows_hud() {
+                    finish_game(&game, &options, playback.as_ref());
+                }
+                break;
+            }
+        }
 
-        let elapsed_seconds: usize = game.total_seconds / 60;
-        draw_text(&mut buffer, WIDTH, 650, 15, "Time(s):", 0xdddddd, 1);
-        draw_number(&mut buffer, WIDTH, 700, 10, elapsed_seconds, 0xaaffaa, 3);
+        let now: Instant = Instant::now();
+        // Clamp long stalls (window drag, breakpoint) so we don't try to catch up for seconds
+        let frame_time: f64 = now
+            .duration_since(last_time)
+            .as_secs_f64()
+            .min(MAX_FRAME_TIME);
+        last_time = now;
 
-        if game.missed_count >= MAX_ESCAPED {
-            println!("Too many missed stars — game over!");
+        // Time only flows while playing, so pausing freezes every timer in the game
+        if screen.is_running() {
+            accumulator += frame_time;
+            let ticks: usize = (accumulator / tick) as usize;
+            accumulator -= ticks as f64 * tick;
 
-            draw_text(&mut buffer, WIDTH, 250, 250, "FAIL!", 0x225599, 16);
+            for _ in 0..ticks {
+                let input: InputState = match &playback {
+                    Some(replay) => match replay.frames.get(game.replay.frames.len()) {
+                        Some(input) => *input,
+                        None => break,
+                    },
+                    None => InputState::from_window(&window, &menus.bindings),
+                };
 
-            // Update the window so the player can see it
-            window.update_with_buffer(&buffer, WIDTH, HEIGHT).unwrap();
+                game.step(&input, audio.as_mut());
 
-            std::thread::sleep(std::time::Duration::from_secs(3));
+                if game.is_game_over() {
+                    break;
+                }
+            }
+        }
 
-            break;
+        // Redraw every frame, even without a new tick, so the interpolation stays smooth
+        let alpha: f32 = (accumulator / tick) as f32;
+        // A normal window is drawn at its own size (a minimised one reports 0x0, so keep the
+        // old canvas then); the other modes draw the play field and let minifb scale it
+        let (canvas_width, canvas_height) = if display.follows_window() {
+            window.get_size()
+        } else {
+            (settings.width, settings.height)
+        };
+        if canvas_width > 0
+            && canvas_height > 0
+            && (canvas_width, canvas_height) != (canvas.width, canvas.height)
+        {
+            canvas = Canvas::new(canvas_width, canvas_height);
         }
+        render_world(&game, &mut canvas, &mut world, settings.pixel_scale, alpha);
+        if screen.shows_hud() {
+            draw_hud(&mut canvas, &game);
+        }
+        screen.draw_overlay(&mut canvas, &game, &menus);
 
-        if game.redemption_flash_timer > 0.0 {
-            game.flash(&mut buffer, draw_x, draw_y, WIDTH, HEIGHT, self::GREEN);
+        if let Some(dir) = &options.dump_frames {
+            dump_frame(&canvas, dir, frames_dumped);
+            frames_dumped += 1;
+        }
+        if window.is_key_pressed(Key::F12, KeyRepeat::No) {
+            save_screenshot(&canvas);
         }
 
         // --- Update window buffer ---
-        window.update_with_buffer(&buffer, WIDTH, HEIGHT).unwrap();
+        window
+            .update_with_buffer(&canvas.buffer, canvas.width, canvas.height)
+            .unwrap();
+
+        // minifb can't restyle a window, so switching modes means opening a new one
+        if window.is_key_pressed(Key::F11, KeyRepeat::No) {
+            let next: DisplayMode = display.toggled(options.display);
+            match open_window(next, settings.width, settings.height) {
+                Ok(new_window) => {
+                    window = new_window;
+                    display = next;
+                }
+                Err(message) => eprintln!("{message}"),
+            }
+        }
+    }
+}
+
+// Replays always restart from their own seed and mode. Otherwise the daily challenge plays today's
+// seed, and any other mode reuses --seed or picks a fresh one.
+fn new_game(
+    options: &CliOptions,
+    settings: &Settings,
+    playback: Option<&Replay>,
+    tick_rate: u32,
+    mode: GameMode,
+) -> GameState {
+    let (seed, mode) = match playback {
+        Some(replay) => (replay.seed, replay.mode),
+        None if mode == GameMode::Daily => (daily_seed(highscores::now()), mode),
+        None => (options.seed.unwrap_or_else(time_seed), mode),
+    };
+    GameState::new(settings, seed, tick_rate, mode)
+}
+
+// The settings file (--settings, or the one in the config directory) with any --set applied on top
+fn load_settings(options: &CliOptions) -> Result<Settings, String> {
+    let mut settings: Settings = match &options.settings {
+        // Unlike the default file, one asked for by name has to exist
+        Some(path) if !path.is_file() => {
+            return Err(format!(
+                "Could not read settings {}: no such file",
+                path.display()
+            ));
+        }
+        Some(path) => Settings::load(path)?,
+        None => match settings::default_path() {
+            Some(path) => Settings::load(&path)?,
+            None => Settings::default(),
+        },
+    };
+    for (key, value) in &options.overrides {
+        settings
+            .set(key, value)
+            .map_err(|message| format!("--set: {message}"))?;
+    }
+    Ok(settings)
+}
+
+// Called once per game when it ends: save what was played, or check it against the replay
+fn finish_game(game: &GameState, options: &CliOptions, playback: Option<&Replay>) {
+    match playback {
+        Some(replay) => report_playback(replay, &game.result()),
+        None => {
+            // With nowhere to keep it (no --record and no data directory) the game isn't saved
+            let Some(path) = options.record.clone().or_else(replay::default_path) else {
+                return;
+            };
+            match game.finished_replay().save(&path) {
+                Ok(()) => println!("Replay saved to {}", path.display()),
+                Err(message) => eprintln!("{message}"),
+            }
+        }
+    }
+}
+
+// Draws the game into `canvas`, going through the smaller `world` canvas when pixels are scaled up
+fn render_world(
+    game: &GameState,
+    canvas: &mut Canvas,
+    world: &mut Canvas,
+    scale: usize,
+    alpha: f32,
+) {
+    if scale <= 1 {
+        game.render(canvas, alpha);
+        return;
+    }
+    let width: usize = canvas.width.div_ceil(scale);
+    let height: usize = canvas.height.div_ceil(scale);
+    if (world.width, world.height) != (width, height) {
+        *world = Canvas::new(width, height);
+    }
+    game.render(world, alpha);
+    canvas.blit_scaled(world, scale);
+}
+
+// Green when there's plenty, amber when low, blinking red while overheated
+fn draw_energy_bar(canvas: &mut Canvas, x: usize, y: usize, game: &GameState) {
+    const WIDTH: usize = 100;
+    const HEIGHT: usize = 8;
+    let energy: &Energy = &game.energy;
+    let color: u32 = if energy.overheated {
+        if ((game.elapsed_seconds * 4.0) as usize).is_multiple_of(2) {
+            0xFF2020
+        } else {
+            0x661010
+        }
+    } else if energy.is_low() {
+        0xFFAA00
+    } else {
+        0x20DD40
+    };
+
+    let filled: usize = (energy.level / MAX_ENERGY * WIDTH as f32).round() as usize;
+    canvas.rect(x as isize, y as isize, WIDTH + 2, HEIGHT + 2, 0x888888);
+    canvas.fill_rect(x as isize + 1, y as isize + 1, filled, HEIGHT, color);
+    let label: &str = if energy.overheated {
+        "OVERHEAT"
+    } else {
+        "ENERGY"
+    };
+    draw_text(canvas, x + WIDTH + 8, y + 1, label, color, 1);
+}
+
+// Shield bar, a pip per point of hull and a ship per life, under the energy bar
+fn draw_health(canvas: &mut Canvas, x: usize, y: usize, health: &Health) {
+    const WIDTH: usize = 100;
+    const PIP: usize = 12;
+    let filled: usize = if health.max_shield > 0.0 {
+        (health.shield / health.max_shield * WIDTH as f32).round() as usize
+    } else {
+        0
+    };
+    canvas.rect(x as isize, y as isize, WIDTH + 2, 8, 0x888888);
+    canvas.fill_rect(x as isize + 1, y as isize + 1, filled, 6, 0x44CCFF);
+    draw_text(canvas, x + WIDTH + 8, y, "SHIELD", 0x44CCFF, 1);
+
+    for i in 0..health.max_hull {
+        let pip_x: isize = (x + i * (PIP + 3)) as isize;
+        if i < health.hull {
+            canvas.fill_rect(pip_x, y as isize + 12, PIP, 6, 0xFF8844);
+        } else {
+            canvas.rect(pip_x, y as isize + 12, PIP, 6, 0x664422);
+        }
+    }
+    let hull_x: usize = x + health.max_hull * (PIP + 3) + 5;
+    draw_text(
+        canvas,
+        hull_x.max(x + WIDTH + 8),
+        y + 12,
+        "HULL",
+        0xFF8844,
+        1,
+    );
+
+    let icon: Ship = Ship::new(0.0, 0.0);
+    for i in 0..health.lives {
+        icon.draw(
+            canvas,
+            (x + 4 + i * 14) as isize,
+            (y + 30) as isize,
+            0xFFFFFF,
+            1,
+        );
+    }
+}
+
+// Across the top middle, with a notch where each phase begins; grey while it can't be hurt
+fn draw_boss_bar(canvas: &mut Canvas, boss: &Boss) {
+    const WIDTH: usize = 300;
+    const HEIGHT: usize = 10;
+    let x: usize = canvas.width.saturating_sub(WIDTH) / 2;
+    let y: usize = 30;
+
+    let label: &str = boss.kind.label();
+    draw_text(
+        canvas,
+        canvas.width.saturating_sub(text_width(label, 2)) / 2,
+        y - 20,
+        label,
+        0xFF6644,
+        2,
+    );
+    let color: u32 = if boss.is_vulnerable() {
+        0xFF3322
+    } else {
+        0x888888
+    };
+    let filled: usize = (boss.health_fraction() * WIDTH as f32).round() as usize;
+    canvas.rect(x as isize, y as isize, WIDTH + 2, HEIGHT + 2, 0xAAAAAA);
+    canvas.fill_rect(x as isize + 1, y as isize + 1, filled, HEIGHT, color);
+    for phase in 1..PHASES {
+        let notch: isize = (x + 1 + WIDTH * phase / PHASES) as isize;
+        canvas.fill_rect(notch, y as isize + 1, 1, HEIGHT, 0xFFFFFF);
+    }
+}
+
+fn draw_hud(canvas: &mut Canvas, game: &GameState) {
+    let (x, y) = anchored(canvas, Anchor::TopLeft, 10, 10, 0, 0);
+    draw_number(canvas, x, y, game.scoring.score, 0xffffff, 4); // white color
+    if game.scoring.multiplier() > 1 {
+        let combo: String = format!("X{}", game.scoring.multiplier());
+        let score_width: usize = text_width(&game.scoring.score.to_string(), 4);
+        draw_text(canvas, x + score_width + 10, y + 7, &combo, 0xFFDD33, 2);
+    }
+    // Misses and damage don't count for anything in zen
+    if game.mode.can_fail() {
+        draw_number(canvas, x, y + 30, game.missed_count, 0xff0000, 4); // red color
+        draw_health(canvas, x, y + 78, &game.health);
+    }
+    draw_energy_bar(canvas, x, y + 62, game);
+    for (i, (kind, left)) in game.boosts.active().into_iter().enumerate() {
+        let timer: String = format!("{} {}", kind.label(), left.ceil() as usize);
+        draw_text(canvas, x, y + 122 + i * 12, &timer, kind.color(), 1);
+    }
+
+    let (x, y) = anchored(
+        canvas,
+        Anchor::BottomLeft,
+        10,
+        6,
+        text_width("eagle", 2),
+        14,
+    );
+    draw_text(canvas, x, y, "eagle", 0xFF00FF00, 2);
+
+    // Right-aligned, so the label moves left as the number grows. Against the clock it counts
+    // down instead, going red for the last ten seconds.
+    let (seconds, label, color) = match game.time_left() {
+        Some(left) if left < 10.0 => (left.ceil() as usize, "Left(s):", 0xFF4444),
+        Some(left) => (left.ceil() as usize, "Left(s):", 0xaaffaa),
+        None => (game.elapsed_seconds as usize, "Time(s):", 0xaaffaa),
+    };
+    let (x, y) = anchored(
+        canvas,
+        Anchor::TopRight,
+        10,
+        10,
+        text_width(&seconds.to_string(), 3),
+        15,
+    );
+    draw_number(canvas, x, y, seconds, color, 3);
+    let label_x: usize = x.saturating_sub(text_width(label, 1) + 8);
+    draw_text(canvas, label_x, y + 5, label, 0xdddddd, 1);
+
+    let progress: String = match game.mode {
+        GameMode::Endless => game.campaign.status(),
+        GameMode::Daily => format!("DAILY  {}", game.campaign.status()),
+        _ => game.mode.label().to_string(),
+    };
+    let (x, y) = anchored(
+        canvas,
+        Anchor::TopRight,
+        10,
+        34,
+        text_width(&progress, 2),
+        14,
+    );
+    draw_text(canvas, x, y, &progress, 0xFFDD88, 2);
+
+    if let Some(boss) = &game.boss {
+        draw_boss_bar(canvas, boss);
+    }
+
+    let weapons: String = format!(
+        "{}  BOMBS {}",
+        game.armory.selected.label(),
+        game.armory.bombs
+    );
+    let (x, y) = anchored(
+        canvas,
+        Anchor::BottomRight,
+        10,
+        6,
+        text_width(&weapons, 2),
+        14,
+    );
+    draw_text(canvas, x, y, &weapons, 0x66FFFF, 2);
+}
+
+fn dump_frame(canvas: &Canvas, dir: &Path, index: usize) {
+    let path: PathBuf = dir.join(format!("frame_{index:06}.png"));
+    if let Err(message) = png::save(canvas, &path) {
+        eprintln!("{message}");
+    }
+}
+
+fn save_screenshot(canvas: &Canvas) {
+    let millis: u128 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis();
+    let path: PathBuf = PathBuf::from(format!("screenshot_{millis}.png"));
+    match png::save(canvas, &path) {
+        Ok(()) => println!("Screenshot saved to {}", path.display()),
+        Err(message) => eprintln!("{message}"),
+    }
+}
+
+// Runs a replay as fast as possible. With a dump directory every tick is also
+// rendered and written out, which is handy for turning a bug report into images.
+fn play_headless(replay: &Replay, dump_frames: Option<&Path>) -> ReplayResult {
+    let mut game: GameState =
+        GameState::new(&replay.settings, replay.seed, replay.tick_rate, replay.mode);
+    let mut audio: NullAudio = NullAudio;
+    let mut canvas: Canvas = Canvas::new(replay.settings.width, replay.settings.height);
+    let mut world: Canvas = Canvas::new(0, 0);
+
+    for (index, input) in replay.frames.iter().enumerate() {
+        game.step(input, &mut audio);
+        if let Some(dir) = dump_frames {
+            render_world(
+                &game,
+                &mut canvas,
+                &mut world,
+                replay.settings.pixel_scale,
+                1.0,
+            );
+            draw_hud(&mut canvas, &game);
+            dump_frame(&canvas, dir, index);
+        }
+        if game.is_game_over() {
+            break;
+        }
+    }
+
+    game.result()
+}
+
+fn report_playback(replay: &Replay, result: &ReplayResult) {
+    let death: String = match result.game_over_frame {
+        Some(tick) => format!("on tick {tick}"),
+        None => "never".to_string(),
+    };
+    println!(
+        "Replay of seed {} ({}): score {}, hits {}, missed {}, game over {}",
+        replay.seed,
+        replay.mode.label(),
+        result.score,
+        result.collision_count,
+        result.missed_count,
+        death
+    );
+
+    match &replay.result {
+        Some(recorded) if recorded == result => println!("Matches the recorded session"),
+        Some(recorded) => println!(
+            "DIFFERS from the recorded session: score {}, hits {}, missed {}, game over {:?}",
+            recorded.score,
+            recorded.collision_count,
+            recorded.missed_count,
+            recorded.game_over_frame
+        ),
+        None => {}
     }
 }
@@ -2,8 +2,10 @@
 pub struct Particle {
     pub x: f32,
     pub y: f32,
+    // Velocity in pixels per second
     pub vx: f32,
     pub vy: f32,
-    pub life: u32,
-    pub initial_life: u32,
+    // Seconds left to live
+    pub life: f32,
+    pub initial_life: f32,
 }
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+// Per-user directories, following each platform's convention. None if the environment
+// doesn't say where home is, in which case callers just don't persist anything.
+pub fn data_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    }
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,607 @@
+use crate::canvas::Canvas;
+use std::fs;
+use std::path::Path;
+
+// Small self-contained PNG support: 8-bit RGB images, zlib data compressed with
+// fixed-Huffman deflate and a single-candidate LZ77 matcher. Plenty for mostly
+// black game frames, and no dependency to vendor.
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub fn encode(canvas: &Canvas) -> Vec<u8> {
+    // Every scanline starts with its filter type; 0 means the bytes are stored as they are
+    let mut raw: Vec<u8> = Vec::with_capacity((canvas.width * 3 + 1) * canvas.height);
+    for row in canvas.buffer.chunks(canvas.width.max(1)) {
+        raw.push(0);
+        for pixel in row {
+            raw.extend_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8]);
+        }
+    }
+
+    let mut ihdr: Vec<u8> = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(canvas.width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(canvas.height as u32).to_be_bytes());
+    // bit depth 8, color type 2 (RGB), deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png: Vec<u8> = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib_compress(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+pub fn save(canvas: &Canvas, path: &Path) -> Result<(), String> {
+    fs::write(path, encode(canvas))
+        .map_err(|e| format!("Could not write image {}: {e}", path.display()))
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start: usize = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc: u32 = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    // CMF/FLG: deflate with a 32K window, no preset dictionary, fastest compression level
+    let mut out: Vec<u8> = vec![0x78, 0x01];
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// A single final block using the fixed Huffman codes from RFC 1951
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer: BitWriter = BitWriter::default();
+    writer.bits(1, 1); // BFINAL
+    writer.bits(1, 2); // BTYPE = fixed Huffman
+
+    let mut head: Vec<usize> = vec![usize::MAX; 1 << HASH_BITS];
+    let mut i: usize = 0;
+    while i < data.len() {
+        let mut match_len: usize = 0;
+        let mut match_dist: usize = 0;
+
+        if i + MIN_MATCH <= data.len() {
+            let slot: usize = hash(&data[i..]);
+            let candidate: usize = head[slot];
+            head[slot] = i;
+
+            if candidate != usize::MAX && i - candidate <= WINDOW_SIZE {
+                let max_len: usize = (data.len() - i).min(MAX_MATCH);
+                let mut len: usize = 0;
+                while len < max_len && data[candidate + len] == data[i + len] {
+                    len += 1;
+                }
+                if len >= MIN_MATCH {
+                    match_len = len;
+                    match_dist = i - candidate;
+                }
+            }
+        }
+
+        if match_len == 0 {
+            write_symbol(&mut writer, data[i] as u16);
+            i += 1;
+            continue;
+        }
+
+        let code: usize = LENGTH_BASE
+            .iter()
+            .rposition(|base| *base as usize <= match_len)
+            .unwrap();
+        write_symbol(&mut writer, 257 + code as u16);
+        writer.bits(
+            (match_len - LENGTH_BASE[code] as usize) as u32,
+            LENGTH_EXTRA[code] as u32,
+        );
+
+        let dist_code: usize = DIST_BASE
+            .iter()
+            .rposition(|base| *base as usize <= match_dist)
+            .unwrap();
+        writer.huffman(dist_code as u32, 5);
+        writer.bits(
+            (match_dist - DIST_BASE[dist_code] as usize) as u32,
+            DIST_EXTRA[dist_code] as u32,
+        );
+
+        // Remember the positions we skipped over so later data can match them too
+        for j in i + 1..(i + match_len).min(data.len().saturating_sub(MIN_MATCH - 1)) {
+            head[hash(&data[j..])] = j;
+        }
+        i += match_len;
+    }
+
+    write_symbol(&mut writer, 256); // end of block
+    writer.finish()
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let value: u32 = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+    (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+// Fixed literal/length code: 0-143 use 8 bits, 144-255 9 bits, 256-279 7 bits, 280-287 8 bits
+fn write_symbol(writer: &mut BitWriter, symbol: u16) {
+    let symbol: u32 = symbol as u32;
+    let (code, len): (u32, u32) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xC0 + symbol - 280, 8),
+    };
+    writer.huffman(code, len);
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    // Plain values are packed least significant bit first
+    fn bits(&mut self, value: u32, len: u32) {
+        self.acc |= value << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are packed most significant bit first
+    fn huffman(&mut self, code: u32, len: u32) {
+        let reversed: u32 = code.reverse_bits() >> (32 - len);
+        self.bits(reversed, len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table: [u32; 256] = [0; 256];
+    let mut n: usize = 0;
+    while n < 256 {
+        let mut c: u32 = n as u32;
+        let mut k: usize = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for byte in bytes {
+        crc = CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFFFFFF
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// Reading is only needed to compare frames against stored images, so it covers
+// 8-bit RGB/RGBA files without interlacing and nothing else, and is built for tests only.
+#[cfg(test)]
+pub mod decode {
+    use super::*;
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Canvas, String> {
+        if bytes.len() < 8 || &bytes[..8] != SIGNATURE {
+            return Err("Not a PNG file".to_string());
+        }
+
+        let mut pos: usize = 8;
+        let mut header: Option<(usize, usize, usize)> = None;
+        let mut compressed: Vec<u8> = Vec::new();
+        while pos + 8 <= bytes.len() {
+            let len: usize = u32::from_be_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind: &[u8] = &bytes[pos + 4..pos + 8];
+            let data: &[u8] = bytes
+                .get(pos + 8..pos + 8 + len)
+                .ok_or("Corrupt PNG: chunk runs past the end of the file")?;
+            pos += 12 + len;
+
+            match kind {
+                b"IHDR" => {
+                    if data.len() != 13 {
+                        return Err("Corrupt PNG: bad IHDR".to_string());
+                    }
+                    let width: usize = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+                    let height: usize = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+                    let channels: usize = match (data[8], data[9], data[12]) {
+                        (8, 2, 0) => 3,
+                        (8, 6, 0) => 4,
+                        _ => return Err("Unsupported PNG: only 8-bit RGB/RGBA".to_string()),
+                    };
+                    header = Some((width, height, channels));
+                }
+                b"IDAT" => compressed.extend_from_slice(data),
+                b"IEND" => break,
+                _ => {}
+            }
+        }
+
+        let (width, height, channels) = header.ok_or("Corrupt PNG: missing IHDR")?;
+        if compressed.len() < 2 {
+            return Err("Corrupt PNG: missing image data".to_string());
+        }
+        let raw: Vec<u8> = inflate(&compressed[2..])?;
+
+        let stride: usize = width * channels;
+        if raw.len() < (stride + 1) * height {
+            return Err("Corrupt PNG: not enough image data".to_string());
+        }
+
+        let mut canvas: Canvas = Canvas::new(width, height);
+        let mut previous: Vec<u8> = vec![0; stride];
+        let mut current: Vec<u8> = vec![0; stride];
+        for y in 0..height {
+            let line: &[u8] = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+            unfilter(line[0], &line[1..], &previous, &mut current, channels)?;
+            for x in 0..width {
+                let p: &[u8] = &current[x * channels..x * channels + 3];
+                canvas.buffer[y * width + x] =
+                    (p[0] as u32) << 16 | (p[1] as u32) << 8 | p[2] as u32;
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+
+        Ok(canvas)
+    }
+
+    pub fn load(path: &Path) -> Result<Canvas, String> {
+        let bytes: Vec<u8> =
+            fs::read(path).map_err(|e| format!("Could not read image {}: {e}", path.display()))?;
+        from_bytes(&bytes)
+    }
+
+    fn unfilter(
+        filter: u8,
+        line: &[u8],
+        previous: &[u8],
+        out: &mut [u8],
+        bpp: usize,
+    ) -> Result<(), String> {
+        for i in 0..line.len() {
+            let left: u8 = if i >= bpp { out[i - bpp] } else { 0 };
+            let up: u8 = previous[i];
+            let up_left: u8 = if i >= bpp { previous[i - bpp] } else { 0 };
+            let predicted: u8 = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(format!("Corrupt PNG: unknown filter {filter}")),
+            };
+            out[i] = line[i].wrapping_add(predicted);
+        }
+        Ok(())
+    }
+
+    fn paeth(a: u8, b: u8, c: u8) -> u8 {
+        let p: i16 = a as i16 + b as i16 - c as i16;
+        let pa: i16 = (p - a as i16).abs();
+        let pb: i16 = (p - b as i16).abs();
+        let pc: i16 = (p - c as i16).abs();
+        if pa <= pb && pa <= pc {
+            a
+        } else if pb <= pc {
+            b
+        } else {
+            c
+        }
+    }
+
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+        bit: u32,
+    }
+
+    impl BitReader<'_> {
+        fn bits(&mut self, len: u32) -> Result<u32, String> {
+            let mut value: u32 = 0;
+            for i in 0..len {
+                let byte: u8 = *self
+                    .bytes
+                    .get(self.pos)
+                    .ok_or("Corrupt PNG: compressed data ends early")?;
+                value |= (((byte >> self.bit) & 1) as u32) << i;
+                self.bit += 1;
+                if self.bit == 8 {
+                    self.bit = 0;
+                    self.pos += 1;
+                }
+            }
+            Ok(value)
+        }
+
+        fn align_to_byte(&mut self) {
+            if self.bit > 0 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+    }
+
+    // Canonical Huffman table: how many codes of each length, and the symbols in code order
+    struct Huffman {
+        counts: [u16; 16],
+        symbols: Vec<u16>,
+    }
+
+    impl Huffman {
+        fn new(lengths: &[u8]) -> Self {
+            let mut counts: [u16; 16] = [0; 16];
+            for len in lengths {
+                counts[*len as usize] += 1;
+            }
+            counts[0] = 0;
+
+            let mut symbols: Vec<u16> = Vec::with_capacity(lengths.len());
+            for len in 1..16 {
+                for (symbol, l) in lengths.iter().enumerate() {
+                    if *l as usize == len {
+                        symbols.push(symbol as u16);
+                    }
+                }
+            }
+            Self { counts, symbols }
+        }
+
+        fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+            let mut code: i32 = 0;
+            let mut first: i32 = 0;
+            let mut index: i32 = 0;
+            for len in 1..16 {
+                code |= reader.bits(1)? as i32;
+                let count: i32 = self.counts[len] as i32;
+                if code - first < count {
+                    return Ok(self.symbols[(index + code - first) as usize]);
+                }
+                index += count;
+                first = (first + count) << 1;
+                code <<= 1;
+            }
+            Err("Corrupt PNG: bad Huffman code".to_string())
+        }
+    }
+
+    pub(super) fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut reader: BitReader = BitReader {
+            bytes: data,
+            pos: 0,
+            bit: 0,
+        };
+        let mut out: Vec<u8> = Vec::new();
+
+        loop {
+            let last: bool = reader.bits(1)? == 1;
+            match reader.bits(2)? {
+                0 => {
+                    reader.align_to_byte();
+                    let len: usize = reader.bits(16)? as usize;
+                    reader.bits(16)?; // one's complement of len
+                    let end: usize = reader.pos + len;
+                    let stored: &[u8] = data
+                        .get(reader.pos..end)
+                        .ok_or("Corrupt PNG: stored block runs past the end")?;
+                    out.extend_from_slice(stored);
+                    reader.pos = end;
+                }
+                1 => {
+                    let mut lengths: [u8; 288] = [8; 288];
+                    lengths[144..256].fill(9);
+                    lengths[256..280].fill(7);
+                    inflate_block(
+                        &mut reader,
+                        &mut out,
+                        &Huffman::new(&lengths),
+                        &Huffman::new(&[5; 30]),
+                    )?;
+                }
+                2 => {
+                    let (literals, distances) = read_dynamic_tables(&mut reader)?;
+                    inflate_block(&mut reader, &mut out, &literals, &distances)?;
+                }
+                _ => return Err("Corrupt PNG: bad block type".to_string()),
+            }
+            if last {
+                return Ok(out);
+            }
+        }
+    }
+
+    fn read_dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+        const ORDER: [usize; 19] = [
+            16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+        ];
+        let literal_count: usize = reader.bits(5)? as usize + 257;
+        let distance_count: usize = reader.bits(5)? as usize + 1;
+        let code_length_count: usize = reader.bits(4)? as usize + 4;
+
+        let mut code_lengths: [u8; 19] = [0; 19];
+        for slot in ORDER.iter().take(code_length_count) {
+            code_lengths[*slot] = reader.bits(3)? as u8;
+        }
+        let code_length_table: Huffman = Huffman::new(&code_lengths);
+
+        let mut lengths: Vec<u8> = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let (value, repeat): (u8, usize) = match code_length_table.decode(reader)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous: u8 =
+                        *lengths.last().ok_or("Corrupt PNG: repeat with no length")?;
+                    (previous, 3 + reader.bits(2)? as usize)
+                }
+                17 => (0, 3 + reader.bits(3)? as usize),
+                _ => (0, 11 + reader.bits(7)? as usize),
+            };
+            lengths.extend(std::iter::repeat_n(value, repeat));
+        }
+        if lengths.len() != literal_count + distance_count {
+            return Err("Corrupt PNG: code lengths overflow".to_string());
+        }
+
+        Ok((
+            Huffman::new(&lengths[..literal_count]),
+            Huffman::new(&lengths[literal_count..]),
+        ))
+    }
+
+    fn inflate_block(
+        reader: &mut BitReader,
+        out: &mut Vec<u8>,
+        literals: &Huffman,
+        distances: &Huffman,
+    ) -> Result<(), String> {
+        loop {
+            let symbol: usize = literals.decode(reader)? as usize;
+            match symbol {
+                0..=255 => out.push(symbol as u8),
+                256 => return Ok(()),
+                257..=285 => {
+                    let code: usize = symbol - 257;
+                    let len: usize = LENGTH_BASE[code] as usize
+                        + reader.bits(LENGTH_EXTRA[code] as u32)? as usize;
+                    let dist_code: usize = distances.decode(reader)? as usize;
+                    if dist_code >= DIST_BASE.len() {
+                        return Err("Corrupt PNG: bad distance code".to_string());
+                    }
+                    let dist: usize = DIST_BASE[dist_code] as usize
+                        + reader.bits(DIST_EXTRA[dist_code] as u32)? as usize;
+                    if dist > out.len() {
+                        return Err("Corrupt PNG: distance too far back".to_string());
+                    }
+                    // Copy byte by byte: the match may overlap the bytes it produces
+                    let start: usize = out.len() - dist;
+                    for i in 0..len {
+                        out.push(out[start + i]);
+                    }
+                }
+                _ => return Err("Corrupt PNG: bad length code".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simple_random::SimpleRng;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+
+    #[test]
+    fn test_header() {
+        let png = encode(&Canvas::new(3, 2));
+        assert_eq!(&png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut canvas = Canvas::new(64, 48);
+        let mut rng = SimpleRng::new(9);
+        for pixel in canvas.buffer.iter_mut().skip(100).step_by(3) {
+            *pixel = rng.next() & 0xFFFFFF;
+        }
+        canvas.fill_rect(10, 10, 30, 20, 0x336699);
+
+        assert_eq!(decode::from_bytes(&encode(&canvas)), Ok(canvas));
+    }
+
+    #[test]
+    fn test_black_frame_compresses() {
+        let canvas = Canvas::new(800, 600);
+        let png = encode(&canvas);
+        assert!(png.len() < 20_000);
+        assert_eq!(decode::from_bytes(&png), Ok(canvas));
+    }
+
+    #[test]
+    fn test_inflates_stored_and_dynamic_blocks() {
+        let stored: [u8; 8] = [0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c'];
+        assert_eq!(decode::inflate(&stored), Ok(b"abc".to_vec()));
+
+        // Written by zlib at level 9, which picked a dynamic Huffman block
+        let dynamic: [u8; 41] = [
+            0x25, 0xCA, 0xB1, 0x0D, 0x00, 0x30, 0x0C, 0x02, 0xC1, 0x55, 0x58, 0x8D, 0xE2, 0xE5,
+            0xE6, 0xF7, 0xAF, 0x63, 0x2B, 0x05, 0x12, 0x87, 0x68, 0x18, 0x5A, 0x10, 0x66, 0x63,
+            0xD7, 0x03, 0x49, 0xFE, 0x88, 0x52, 0xB9, 0x9B, 0xB9, 0x76, 0x5E, 0x44, 0x1E,
+        ];
+        let expected: &[u8] = b"a egeaaeeleegleelaegegee   aeeleeelleale egel ealelleaege le";
+        assert_eq!(decode::inflate(&dynamic), Ok(expected.to_vec()));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(decode::from_bytes(b"not a png").is_err());
+        let mut png = encode(&Canvas::new(4, 4));
+        png.truncate(40);
+        assert!(decode::from_bytes(&png).is_err());
+    }
+}
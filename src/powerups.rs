@@ -0,0 +1,175 @@
+use crate::simple_random::SimpleRng;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    // A bubble around the ship that catches stars before they reach it
+    Shield,
+    // Pulls big stars in towards the ship
+    Magnet,
+    // Big stars come in at half speed
+    TimeSlow,
+    // Bombs reach twice as far
+    WideBlast,
+    // Takes back a few missed stars, straight away
+    ExtraLife,
+}
+
+pub const POWER_UP_KINDS: [PowerUpKind; 5] = [
+    PowerUpKind::Shield,
+    PowerUpKind::Magnet,
+    PowerUpKind::TimeSlow,
+    PowerUpKind::WideBlast,
+    PowerUpKind::ExtraLife,
+];
+
+// Chance that a destroyed big star leaves a power-up behind
+const DROP_CHANCE: f32 = 0.15;
+// How fast pickups come towards the camera, in the big stars' depth units per second
+const DRIFT_SPEED: f32 = 0.25;
+
+impl PowerUpKind {
+    // Letter drawn on the pickup and name shown on the HUD
+    pub fn letter(self) -> char {
+        match self {
+            PowerUpKind::Shield => 'S',
+            PowerUpKind::Magnet => 'M',
+            PowerUpKind::TimeSlow => 'T',
+            PowerUpKind::WideBlast => 'W',
+            PowerUpKind::ExtraLife => '+',
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "SHIELD",
+            PowerUpKind::Magnet => "MAGNET",
+            PowerUpKind::TimeSlow => "SLOW",
+            PowerUpKind::WideBlast => "WIDE",
+            PowerUpKind::ExtraLife => "EXTRA",
+        }
+    }
+
+    pub fn color(self) -> u32 {
+        match self {
+            PowerUpKind::Shield => 0x44CCFF,
+            PowerUpKind::Magnet => 0xFF4488,
+            PowerUpKind::TimeSlow => 0xAA88FF,
+            PowerUpKind::WideBlast => 0xFFAA22,
+            PowerUpKind::ExtraLife => 0x44FF66,
+        }
+    }
+
+    // Seconds the effect lasts; instant ones have none
+    fn duration(self) -> f32 {
+        match self {
+            PowerUpKind::Shield => 8.0,
+            PowerUpKind::Magnet => 6.0,
+            PowerUpKind::TimeSlow => 5.0,
+            PowerUpKind::WideBlast => 10.0,
+            PowerUpKind::ExtraLife => 0.0,
+        }
+    }
+}
+
+// A pickup floating in the same space as the big stars, projected the same way
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerUp {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub kind: PowerUpKind,
+}
+
+impl PowerUp {
+    // Sometimes returns a pickup left where a star at (x, y, z) was destroyed
+    pub fn drop(x: f32, y: f32, z: f32, rng: &mut SimpleRng) -> Option<PowerUp> {
+        if rng.random_range_f32(0.0..1.0) >= DROP_CHANCE {
+            return None;
+        }
+        let kind: PowerUpKind =
+            POWER_UP_KINDS[rng.random_range_u32(0..POWER_UP_KINDS.len() as u32) as usize];
+        Some(PowerUp { x, y, z, kind })
+    }
+
+    pub fn drift(&mut self, dt: f32) {
+        self.z -= DRIFT_SPEED * dt;
+    }
+
+    pub fn project(&self, width: usize, height: usize) -> (f32, f32) {
+        (
+            self.x / self.z * width as f32 / 2.0 + width as f32 / 2.0,
+            self.y / self.z * height as f32 / 2.0 + height as f32 / 2.0,
+        )
+    }
+}
+
+// Seconds left on each timed power-up, indexed like POWER_UP_KINDS
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Boosts {
+    timers: [f32; POWER_UP_KINDS.len()],
+}
+
+impl Boosts {
+    // Picking up one that's already running starts it over
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        self.timers[kind as usize] = kind.duration();
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        for timer in self.timers.iter_mut() {
+            *timer = (*timer - dt).max(0.0);
+        }
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.timers[kind as usize] > 0.0
+    }
+
+    // The running ones with their seconds left, for the HUD
+    pub fn active(&self) -> Vec<(PowerUpKind, f32)> {
+        POWER_UP_KINDS
+            .iter()
+            .map(|kind| (*kind, self.timers[*kind as usize]))
+            .filter(|(_, left)| *left > 0.0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boosts_run_out() {
+        let mut boosts = Boosts::default();
+        boosts.activate(PowerUpKind::Magnet);
+        boosts.activate(PowerUpKind::ExtraLife);
+        assert_eq!(boosts.active(), vec![(PowerUpKind::Magnet, 6.0)]);
+
+        boosts.tick(5.0);
+        assert!(boosts.is_active(PowerUpKind::Magnet));
+        boosts.activate(PowerUpKind::Magnet);
+        boosts.tick(5.0);
+        assert!(boosts.is_active(PowerUpKind::Magnet));
+        boosts.tick(1.0);
+        assert!(boosts.active().is_empty());
+    }
+
+    #[test]
+    fn test_drops_are_occasional_and_repeatable() {
+        let drops = |seed: u64| -> Vec<Option<PowerUp>> {
+            let mut rng = SimpleRng::new(seed);
+            (0..1000)
+                .map(|_| PowerUp::drop(0.1, 0.2, 1.5, &mut rng))
+                .collect()
+        };
+        let first = drops(3);
+        assert_eq!(first, drops(3));
+
+        let count: usize = first.iter().flatten().count();
+        assert!((100..200).contains(&count), "{count} drops");
+        for kind in POWER_UP_KINDS {
+            assert!(first.iter().flatten().any(|p| p.kind == kind));
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use crate::game_mode::GameMode;
+use crate::input::InputState;
+use crate::paths;
+use crate::settings::Settings;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// File layout (little endian):
+//   "EGLR" | version: u8 | seed: u64 | tick rate: u16 (since v2)
+//   settings length: u16 | settings file text (since v4) | mode: u8 (since v7)
+//   frame count: u32
+//   runs of (input, run length: varint) until frame count is reached, where input is
+//   buttons: u8, x axis: i8, y axis: i8 (since v3) or one byte of key bits (v1, v2); buttons
+//   is only fire up to v4, and adds next weapon and bomb bits in v5
+//   collisions: u32 | score: u32 (since v6) | missed: u32
+//   game over frame: u32 (u32::MAX if the run never ended)
+// v8 is laid out like v7; only the simulation changed
+const MAGIC: &[u8; 4] = b"EGLR";
+const VERSION: u8 = 8;
+// Files older than this ran a different simulation (controls, stars, scoring, damage), so the same
+// inputs won't play out the same. Any change that makes them play out differently bumps VERSION
+// and moves this up to it
+const SIMULATION_VERSION: u8 = 8;
+const NO_GAME_OVER: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayResult {
+    pub collision_count: usize,
+    // Before v6 the score was the number of collisions
+    pub score: usize,
+    pub missed_count: usize,
+    pub game_over_frame: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    // Format the file was written in; new recordings are the current one
+    pub version: u8,
+    pub seed: u64,
+    // Simulation ticks per second; one frame below is one tick
+    pub tick_rate: u32,
+    // Settings the game ran with; older files predate settings and get the defaults
+    pub settings: Settings,
+    // Older files predate modes and were all endless
+    pub mode: GameMode,
+    pub frames: Vec<InputState>,
+    // Filled in when the session ends, so playback can be checked against it
+    pub result: Option<ReplayResult>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, tick_rate: u32, settings: Settings, mode: GameMode) -> Self {
+        Self {
+            version: VERSION,
+            seed,
+            tick_rate,
+            settings,
+            mode,
+            frames: Vec::new(),
+            result: None,
+        }
+    }
+
+    // Whether playing it back can't be expected to match what was recorded
+    pub fn is_outdated(&self) -> bool {
+        self.version < SIMULATION_VERSION
+    }
+
+    pub fn record(&mut self, input: &InputState) {
+        self.frames.push(*input);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.tick_rate as u16).to_le_bytes());
+        let settings: String = self.settings.to_text();
+        bytes.extend_from_slice(&(settings.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(settings.as_bytes());
+        bytes.push(self.mode.id());
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        // Held keys repeat for many frames, so run-length encoding keeps files tiny
+        let mut i: usize = 0;
+        while i < self.frames.len() {
+            let input: InputState = self.frames[i];
+            let mut run: usize = 1;
+            while i + run < self.frames.len() && self.frames[i + run] == input {
+                run += 1;
+            }
+            bytes.extend_from_slice(&input.to_bytes());
+            write_varint(&mut bytes, run as u32);
+            i += run;
+        }
+
+        let result: ReplayResult = self.result.unwrap_or(ReplayResult {
+            collision_count: 0,
+            score: 0,
+            missed_count: 0,
+            game_over_frame: None,
+        });
+        bytes.extend_from_slice(&(result.collision_count as u32).to_le_bytes());
+        bytes.extend_from_slice(&(result.score as u32).to_le_bytes());
+        bytes.extend_from_slice(&(result.missed_count as u32).to_le_bytes());
+        let game_over_frame: u32 = result
+            .game_over_frame
+            .map_or(NO_GAME_OVER, |frame| frame as u32);
+        bytes.extend_from_slice(&game_over_frame.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, String> {
+        let mut reader: Reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err("Not an eagle replay file".to_string());
+        }
+        let version: u8 = reader.u8()?;
+        if version == 0 || version > VERSION {
+            return Err(format!("Unsupported replay version {version}"));
+        }
+
+        let seed: u64 = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        // Version 1 files were always recorded at 60 ticks per second
+        let tick_rate: u32 = match version {
+            1 => 60,
+            _ => u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as u32,
+        };
+        if tick_rate == 0 {
+            return Err("Corrupt replay: tick rate is zero".to_string());
+        }
+        let settings: Settings = if version >= 4 {
+            let len: usize = u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as usize;
+            let text: &str = std::str::from_utf8(reader.take(len)?)
+                .map_err(|_| "Corrupt replay: settings are not text".to_string())?;
+            Settings::from_text(text).map_err(|errors| format!("Corrupt replay: {errors}"))?
+        } else {
+            Settings::default()
+        };
+        let mode: GameMode = if version >= 7 {
+            GameMode::from_id(reader.u8()?).ok_or("Corrupt replay: unknown mode")?
+        } else {
+            GameMode::Endless
+        };
+        let frame_count: usize = reader.u32()? as usize;
+
+        // The count comes from the file, so reserve no more than its remaining bytes could hold
+        let mut frames: Vec<InputState> = Vec::with_capacity(frame_count.min(reader.remaining()));
+        while frames.len() < frame_count {
+            let input: InputState = match version {
+                1 | 2 => InputState::from_key_bits(reader.u8()?),
+                _ => InputState::from_bytes(reader.take(3)?.try_into().unwrap()),
+            };
+            let run: usize = reader.varint()? as usize;
+            if run == 0 || frames.len() + run > frame_count {
+                return Err("Corrupt replay: bad run length".to_string());
+            }
+            frames.extend(std::iter::repeat_n(input, run));
+        }
+
+        let collision_count: usize = reader.u32()? as usize;
+        let score: usize = match version {
+            1..=5 => collision_count,
+            _ => reader.u32()? as usize,
+        };
+        let missed_count: usize = reader.u32()? as usize;
+        let game_over_frame: Option<usize> = match reader.u32()? {
+            NO_GAME_OVER => None,
+            frame => Some(frame as usize),
+        };
+
+        Ok(Replay {
+            version,
+            seed,
+            tick_rate,
+            settings,
+            mode,
+            frames,
+            result: Some(ReplayResult {
+                collision_count,
+                score,
+                missed_count,
+                game_over_frame,
+            }),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+        }
+        fs::write(path, self.to_bytes())
+            .map_err(|e| format!("Could not write replay {}: {e}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let bytes: Vec<u8> =
+            fs::read(path).map_err(|e| format!("Could not read replay {}: {e}", path.display()))?;
+        Replay::from_bytes(&bytes)
+    }
+}
+
+// Where the last game goes without --record: next to the high scores, in the user data directory
+pub fn default_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("eagle").join("last.replay"))
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end: usize = self.pos + len;
+        if end > self.bytes.len() {
+            return Err("Corrupt replay: unexpected end of file".to_string());
+        }
+        let slice: &[u8] = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn varint(&mut self) -> Result<u32, String> {
+        let mut value: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte: u8 = self.u8()?;
+            value |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Corrupt replay: varint too long".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Replay {
+        let settings = Settings {
+            max_escaped: 25,
+            ..Settings::default()
+        };
+        let mut replay = Replay::new(0xDEADBEEF, 120, settings, GameMode::TimeAttack);
+        for frame in 0..1000 {
+            let x: f32 = if frame < 300 { -1.0 } else { 0.0 };
+            let y: f32 = if frame % 7 == 0 { -0.5 } else { 0.0 };
+            replay.record(&InputState::new((x, y), frame > 900));
+        }
+        replay.result = Some(ReplayResult {
+            collision_count: 12,
+            score: 340,
+            missed_count: 10,
+            game_over_frame: Some(999),
+        });
+        replay
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let replay = sample();
+        assert!(!replay.is_outdated());
+        // Time attack is stored as 1, and must stay so for old files to read the same
+        let bytes: Vec<u8> = replay.to_bytes();
+        assert_eq!(bytes[17 + replay.settings.to_text().len()], 1);
+        assert_eq!(Replay::from_bytes(&bytes), Ok(replay));
+    }
+
+    #[test]
+    fn test_older_simulations_are_outdated() {
+        // The layout hasn't changed since v7, but the game has
+        let mut bytes: Vec<u8> = sample().to_bytes();
+        bytes[4] = 7;
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        assert_eq!(replay.mode, GameMode::TimeAttack);
+        assert!(replay.is_outdated());
+    }
+
+    #[test]
+    fn test_round_trip_without_game_over() {
+        let mut replay = sample();
+        replay.result = Some(ReplayResult {
+            collision_count: 3,
+            score: 30,
+            missed_count: 1,
+            game_over_frame: None,
+        });
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()), Ok(replay));
+    }
+
+    #[test]
+    fn test_held_keys_compress() {
+        let mut replay = Replay::new(1, 60, Settings::default(), GameMode::Endless);
+        for _ in 0..10_000 {
+            replay.record(&InputState::new((1.0, 0.0), false));
+        }
+        // header + settings + one run + result block
+        let settings_len: usize = replay.settings.to_text().len();
+        assert!(replay.to_bytes().len() < 44 + settings_len);
+    }
+
+    // Hand-built old-format file: held left for 3 frames, then right + fire for 2
+    fn key_bits_replay(version: u8) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.push(version);
+        bytes.extend_from_slice(&7u64.to_le_bytes());
+        if version == 2 {
+            bytes.extend_from_slice(&120u16.to_le_bytes());
+        }
+        bytes.extend_from_slice(&5u32.to_le_bytes());
+        bytes.extend_from_slice(&[0b00001, 3, 0b10010, 2]);
+        for value in [1u32, 2, NO_GAME_OVER] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_reads_key_bit_versions() {
+        for (version, tick_rate) in [(1, 60), (2, 120)] {
+            let replay = Replay::from_bytes(&key_bits_replay(version)).unwrap();
+            assert_eq!(replay.seed, 7);
+            assert_eq!(replay.tick_rate, tick_rate);
+            assert_eq!(replay.settings, Settings::default());
+            assert_eq!(replay.mode, GameMode::Endless);
+            let left = InputState::new((-1.0, 0.0), false);
+            let right_fire = InputState::new((1.0, 0.0), true);
+            assert_eq!(
+                replay.frames,
+                vec![left, left, left, right_fire, right_fire]
+            );
+            assert!(replay.is_outdated());
+            let result = replay.result.unwrap();
+            assert_eq!(result.missed_count, 2);
+            assert_eq!(result.score, result.collision_count);
+        }
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(Replay::from_bytes(b"nope").is_err());
+        let mut bytes = sample().to_bytes();
+        bytes.truncate(bytes.len() - 5);
+        assert!(Replay::from_bytes(&bytes).is_err());
+        bytes[4] = 99;
+        assert!(Replay::from_bytes(&bytes).is_err());
+
+        // A frame count far beyond what the file holds is an error, not a huge allocation
+        let mut bytes = key_bits_replay(1);
+        bytes[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes.truncate(21);
+        assert!(Replay::from_bytes(&bytes).is_err());
+    }
+}
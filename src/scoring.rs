@@ -0,0 +1,128 @@
+// Hits in quick succession build a combo; every COMBO_STEP hits in a row add one to the
+// multiplier, up to MAX_MULTIPLIER. Letting COMBO_WINDOW pass without a hit starts over.
+const COMBO_WINDOW: f32 = 1.5;
+const COMBO_STEP: usize = 3;
+const MAX_MULTIPLIER: usize = 8;
+// Points for a star at depth 1; farther stars are harder to hit and worth proportionally more
+const POINTS_PER_DEPTH: f32 = 10.0;
+const MIN_POINTS: usize = 5;
+// Flat bonus for letting a star brush past the ship
+const NEAR_MISS_POINTS: usize = 5;
+
+// How long floating score text lasts and how fast it rises, in pixels per second
+const TEXT_LIFE: f32 = 0.8;
+const TEXT_RISE: f32 = 40.0;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scoring {
+    pub score: usize,
+    // Hits in the current chain
+    pub combo: usize,
+    // Seconds left to keep the chain going
+    combo_timer: f32,
+}
+
+impl Scoring {
+    pub fn multiplier(&self) -> usize {
+        (1 + self.combo / COMBO_STEP).min(MAX_MULTIPLIER)
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.combo_timer -= dt;
+        if self.combo_timer <= 0.0 {
+            self.combo = 0;
+        }
+    }
+
+    // Scores a destroyed star that was at depth `z` and is worth `worth` times the usual,
+    // returning the points it gave
+    pub fn hit(&mut self, z: f32, worth: f32) -> usize {
+        let base: usize = ((POINTS_PER_DEPTH * z * worth).round() as usize).max(MIN_POINTS);
+        let points: usize = base * self.multiplier();
+        self.combo += 1;
+        self.combo_timer = COMBO_WINDOW;
+        self.score += points;
+        points
+    }
+
+    // A star that passed close without hitting; doesn't touch the combo
+    pub fn near_miss(&mut self) -> usize {
+        self.score += NEAR_MISS_POINTS;
+        NEAR_MISS_POINTS
+    }
+}
+
+// Score text that rises from where points were won and fades out
+#[derive(Clone, Debug, PartialEq)]
+pub struct FloatingText {
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+    pub color: u32,
+    pub life: f32,
+}
+
+impl FloatingText {
+    pub fn new(x: f32, y: f32, text: String, color: u32) -> Self {
+        Self {
+            x,
+            y,
+            text,
+            color,
+            life: TEXT_LIFE,
+        }
+    }
+
+    // False once it has faded away
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.y -= TEXT_RISE * dt;
+        self.life -= dt;
+        self.life > 0.0
+    }
+
+    // 1.0 when new, down to 0.0 when gone
+    pub fn fade(&self) -> f32 {
+        (self.life / TEXT_LIFE).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chained_hits_build_a_multiplier() {
+        let mut scoring = Scoring::default();
+        let points: Vec<usize> = (0..7).map(|_| scoring.hit(1.0, 1.0)).collect();
+        assert_eq!(points, vec![10, 10, 10, 20, 20, 20, 30]);
+        assert_eq!(scoring.score, 120);
+
+        // The chain survives short gaps but not long ones
+        scoring.tick(1.0);
+        assert_eq!(scoring.multiplier(), 3);
+        scoring.tick(1.0);
+        assert_eq!(scoring.multiplier(), 1);
+        assert_eq!(scoring.hit(1.0, 1.0), 10);
+    }
+
+    #[test]
+    fn test_distant_stars_score_more() {
+        let mut scoring = Scoring::default();
+        assert_eq!(scoring.hit(3.0, 1.0), 30);
+        let mut scoring = Scoring::default();
+        assert_eq!(scoring.hit(3.0, 2.0), 60);
+        let mut scoring = Scoring::default();
+        assert_eq!(scoring.hit(0.2, 1.0), MIN_POINTS);
+        assert_eq!(scoring.near_miss(), NEAR_MISS_POINTS);
+        assert_eq!(scoring.combo, 1);
+    }
+
+    #[test]
+    fn test_floating_text_rises_and_fades() {
+        let mut text = FloatingText::new(10.0, 100.0, "+10".to_string(), 0xFFFFFF);
+        assert!(text.update(0.4));
+        assert_eq!(text.y, 84.0);
+        assert!((text.fade() - 0.5).abs() < 1e-6);
+        assert!(!text.update(0.4));
+    }
+}
@@ -0,0 +1,756 @@
+use crate::bindings::{ACTIONS, Action, Bindings, key_name};
+use crate::canvas::Canvas;
+use crate::draw_text::{draw_number, draw_text, text_width};
+use crate::game_mode::GameMode;
+use crate::game_state::GameState;
+use crate::highscores::{HighScore, HighScores, NAME_LEN};
+use minifb::{Key, KeyRepeat, Window};
+
+// Which part of the program owns the main loop right now
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Screen {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+    // Typing initials for a score that made the table
+    NameEntry,
+    HighScores,
+    // Viewing and rebinding the keys
+    Controls,
+}
+
+// Menu keys, counted once per press so holding a key doesn't skip through screens
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MenuInput {
+    pub confirm: bool,
+    pub pause: bool,
+    pub scores: bool,
+    pub back: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub backspace: bool,
+    pub controls: bool,
+    // Whatever key went down this frame, for typing and for rebinding
+    pub key: Option<Key>,
+    pub letter: Option<char>,
+}
+
+impl MenuInput {
+    // Pause, quit and the menu directions follow the player's bindings. Escape always
+    // works as "back" too, so the menus can't be locked by unbinding quit.
+    pub fn from_window(window: &Window, bindings: &Bindings) -> Self {
+        let key: Option<Key> = window.get_keys_pressed(KeyRepeat::No).first().copied();
+        Self {
+            confirm: window.is_key_pressed(Key::Enter, KeyRepeat::No),
+            pause: bindings.is_pressed(window, Action::Pause),
+            scores: window.is_key_pressed(Key::H, KeyRepeat::No),
+            back: bindings.is_pressed(window, Action::Quit)
+                || window.is_key_pressed(Key::Escape, KeyRepeat::No),
+            up: bindings.is_pressed(window, Action::MoveUp),
+            down: bindings.is_pressed(window, Action::MoveDown),
+            left: bindings.is_pressed(window, Action::MoveLeft),
+            right: bindings.is_pressed(window, Action::MoveRight),
+            backspace: window.is_key_pressed(Key::Backspace, KeyRepeat::No),
+            controls: window.is_key_pressed(Key::C, KeyRepeat::No),
+            key,
+            letter: key.and_then(key_letter),
+        }
+    }
+}
+
+fn key_letter(key: Key) -> Option<char> {
+    let index: u32 = (key as u32).checked_sub(Key::A as u32)?;
+    (index < 26).then(|| (b'A' + index as u8) as char)
+}
+
+// Initials being typed on the name entry screen
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Initials {
+    pub letters: [u8; NAME_LEN],
+    pub cursor: usize,
+}
+
+impl Default for Initials {
+    fn default() -> Self {
+        Self {
+            letters: [b'A'; NAME_LEN],
+            cursor: 0,
+        }
+    }
+}
+
+impl Initials {
+    // Typing a letter fills the current slot; up/down cycle it like an arcade cabinet
+    pub fn edit(&mut self, menu: &MenuInput) {
+        let slot: &mut u8 = &mut self.letters[self.cursor];
+        if menu.up {
+            *slot = if *slot == b'Z' { b'A' } else { *slot + 1 };
+        }
+        if menu.down {
+            *slot = if *slot == b'A' { b'Z' } else { *slot - 1 };
+        }
+        if let Some(letter) = menu.letter {
+            *slot = letter as u8;
+            self.cursor = (self.cursor + 1).min(NAME_LEN - 1);
+        } else if menu.right {
+            self.cursor = (self.cursor + 1).min(NAME_LEN - 1);
+        } else if menu.left || menu.backspace {
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.letters).into_owned()
+    }
+}
+
+// Cursor on the controls screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ControlsMenu {
+    pub selected: usize,
+    // Waiting for the key to add to the selected action
+    pub capturing: bool,
+}
+
+impl ControlsMenu {
+    pub fn edit(&mut self, menu: &MenuInput, bindings: &mut Bindings) {
+        if self.capturing {
+            // Escape cancels, so it can never end up bound to anything but quit
+            if let Some(key) = menu.key {
+                if key != Key::Escape {
+                    bindings.bind(ACTIONS[self.selected], key);
+                }
+                self.capturing = false;
+            }
+            return;
+        }
+
+        if menu.up {
+            self.selected = (self.selected + ACTIONS.len() - 1) % ACTIONS.len();
+        }
+        if menu.down {
+            self.selected = (self.selected + 1) % ACTIONS.len();
+        }
+        if menu.confirm {
+            self.capturing = true;
+        }
+        if menu.backspace {
+            bindings.clear(ACTIONS[self.selected]);
+        }
+        if menu.key == Some(Key::F5) {
+            *bindings = Bindings::default();
+        }
+    }
+}
+
+// What the menu screens show besides the game itself
+#[derive(Clone, Debug, Default)]
+pub struct Menus {
+    pub high_scores: HighScores,
+    pub initials: Initials,
+    // Row of the table to highlight, right after a new entry
+    pub highlight: Option<usize>,
+    pub bindings: Bindings,
+    pub controls: ControlsMenu,
+    // Mode the next game is played in, and whose table is shown
+    pub mode: GameMode,
+}
+
+impl Menus {
+    // Left and right step through the modes on the title and high-score screens
+    pub fn pick_mode(&mut self, menu: &MenuInput) {
+        if menu.left || menu.right {
+            self.mode = self.mode.cycle(menu.left);
+            self.highlight = None;
+        }
+    }
+}
+
+// What the screens need to know about the game to decide where to go next
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    // The current game can't continue
+    pub game_over: bool,
+    // Its score earns a place on the table
+    pub high_score: bool,
+    // The controls screen is waiting for a key and wants every press
+    pub capturing_key: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    Stay,
+    Go(Screen),
+    // Throw the current game away and start playing a fresh one
+    NewGame,
+    // Put the finished game on the high-score table under the typed initials
+    SubmitScore,
+    Quit,
+}
+
+impl Screen {
+    pub fn next(self, menu: &MenuInput, status: &Status) -> Transition {
+        match self {
+            Screen::Title if menu.confirm => Transition::NewGame,
+            Screen::Title if menu.scores => Transition::Go(Screen::HighScores),
+            Screen::Title if menu.controls => Transition::Go(Screen::Controls),
+            Screen::Playing if status.game_over && status.high_score => {
+                Transition::Go(Screen::NameEntry)
+            }
+            Screen::Playing if status.game_over => Transition::Go(Screen::GameOver),
+            Screen::Playing if menu.pause => Transition::Go(Screen::Paused),
+            Screen::Paused if menu.pause || menu.confirm => Transition::Go(Screen::Playing),
+            // Leaves the game for the title, the only way out of one that can't end; it ends the game
+            // all the same, so a score good enough still goes on the table first
+            Screen::Paused if menu.back && status.high_score => Transition::Go(Screen::NameEntry),
+            Screen::Paused if menu.back => Transition::Go(Screen::Title),
+            Screen::GameOver if menu.confirm => Transition::NewGame,
+            Screen::GameOver if menu.scores => Transition::Go(Screen::HighScores),
+            Screen::NameEntry if menu.confirm => Transition::SubmitScore,
+            // Escape skips the entry instead of quitting; letters typed here are not shortcuts
+            Screen::NameEntry if menu.back => Transition::Go(Screen::GameOver),
+            Screen::NameEntry => Transition::Stay,
+            Screen::HighScores if menu.back || menu.confirm || menu.scores => {
+                Transition::Go(Screen::Title)
+            }
+            Screen::Controls if status.capturing_key => Transition::Stay,
+            Screen::Controls if menu.back => Transition::Go(Screen::Title),
+            Screen::Controls => Transition::Stay,
+            _ if menu.back => Transition::Quit,
+            _ => Transition::Stay,
+        }
+    }
+
+    // Only the simulation screen advances the game; everything else freezes it
+    pub fn is_running(self) -> bool {
+        self == Screen::Playing
+    }
+
+    // Whether the in-game HUD belongs on this screen
+    pub fn shows_hud(self) -> bool {
+        matches!(self, Screen::Playing | Screen::Paused)
+    }
+
+    // Whether going to `next` leaves the game behind for good, so it should be saved or reported
+    pub fn ends_game(self, next: Screen) -> bool {
+        self.shows_hud() && !next.shows_hud()
+    }
+
+    // Draw this screen's text over an already rendered frame of `game`
+    pub fn draw_overlay(self, canvas: &mut Canvas, game: &GameState, menus: &Menus) {
+        let (left, top) = layout_origin(canvas);
+        match self {
+            // Between waves the banner says what's coming
+            Screen::Playing => {
+                for (i, line) in game.campaign.banner().iter().enumerate() {
+                    let (y, scale, color) = match i {
+                        0 => (top + 140, 6, 0xFFDD33),
+                        _ => (top + 180 + i * 36, 3, 0xFFFFFF),
+                    };
+                    draw_centered(canvas, y, line, color, scale);
+                }
+            }
+            Screen::Title => {
+                dim(canvas);
+                draw_centered(canvas, top + 120, "EAGLE", 0x00FF00, 12);
+                draw_mode(canvas, top + 222, menus.mode);
+                draw_centered(canvas, top + 252, menus.mode.description(), 0xAAAAAA, 2);
+                draw_centered(canvas, top + 285, "PRESS ENTER TO START", 0xFFFFFF, 3);
+                draw_centered(
+                    canvas,
+                    top + 325,
+                    "H: HIGH SCORES   C: CONTROLS   ESC: QUIT",
+                    0xAAAAAA,
+                    2,
+                );
+                // A taste of the mode's table; H shows all of it
+                draw_score_table(canvas, top + 380, &menus.high_scores, menus.mode, 3, None);
+            }
+            Screen::Paused => {
+                dim(canvas);
+                draw_centered(canvas, top + 250, "PAUSED", 0xFFFFFF, 8);
+                draw_centered(canvas, top + 340, "P: RESUME   ESC: TITLE", 0xAAAAAA, 2);
+            }
+            Screen::GameOver => {
+                dim(canvas);
+                let (title, reason) = if game.health.lives == 0 {
+                    ("FAIL!", "OUT OF SHIPS")
+                } else if game.time_left() == Some(0.0) {
+                    ("TIME!", "TIME ATTACK IS OVER")
+                } else {
+                    ("FAIL!", "TOO MANY STARS GOT AWAY")
+                };
+                draw_centered(canvas, top + 90, title, 0x225599, 12);
+                draw_centered(canvas, top + 190, reason, 0xAAAAAA, 3);
+
+                let x: usize = left + LAYOUT_WIDTH / 2 - 170;
+                draw_stat(canvas, x, top + 240, "SCORE:", game.scoring.score as u64);
+                draw_stat(canvas, x, top + 280, "MISSED:", game.missed_count as u64);
+                draw_stat(
+                    canvas,
+                    x,
+                    top + 320,
+                    "TIME(S):",
+                    game.elapsed_seconds as u64,
+                );
+                draw_stat(canvas, x, top + 360, "SEED:", game.seed);
+
+                draw_centered(
+                    canvas,
+                    top + 450,
+                    "ENTER: PLAY AGAIN   H: HIGH SCORES   ESC: QUIT",
+                    0xAAAAAA,
+                    2,
+                );
+            }
+            Screen::NameEntry => {
+                dim(canvas);
+                draw_centered(canvas, top + 90, "NEW HIGH SCORE!", 0xFFFF00, 6);
+                let x: usize = left + LAYOUT_WIDTH / 2 - 170;
+                draw_stat(canvas, x, top + 200, "SCORE:", game.scoring.score as u64);
+                draw_stat(
+                    canvas,
+                    x,
+                    top + 240,
+                    "TIME(S):",
+                    game.elapsed_seconds as u64,
+                );
+
+                // Three big letters with the one being edited underlined
+                let scale: usize = 10;
+                let name: String = menus.initials.name();
+                let name_x: usize = canvas.width.saturating_sub(text_width(&name, scale)) / 2;
+                draw_text(canvas, name_x, top + 310, &name, 0xFFFFFF, scale);
+                let cursor_x: usize = name_x + menus.initials.cursor * 6 * scale;
+                canvas.fill_rect(
+                    cursor_x as isize,
+                    (top + 390) as isize,
+                    5 * scale,
+                    scale / 2,
+                    0x00FF00,
+                );
+
+                draw_centered(canvas, top + 450, "TYPE OR UP/DOWN: LETTER", 0xAAAAAA, 2);
+                draw_centered(canvas, top + 480, "ENTER: SAVE   ESC: SKIP", 0xAAAAAA, 2);
+            }
+            Screen::Controls => {
+                dim(canvas);
+                draw_centered(canvas, top + 60, "CONTROLS", 0xFFFF00, 6);
+                for (i, action) in ACTIONS.iter().enumerate() {
+                    let y: usize = top + 150 + i * 34;
+                    let selected: bool = i == menus.controls.selected;
+                    let color: u32 = if selected { 0x00FF00 } else { 0xFFFFFF };
+                    if selected {
+                        canvas.fill_rect((left + 66) as isize, y as isize + 4, 8, 6, color);
+                    }
+                    draw_text(canvas, left + 90, y, action.label(), color, 2);
+
+                    let keys: String = if selected && menus.controls.capturing {
+                        "PRESS A KEY...".to_string()
+                    } else if menus.bindings.keys(*action).is_empty() {
+                        "-".to_string()
+                    } else {
+                        let names: Vec<String> = menus
+                            .bindings
+                            .keys(*action)
+                            .iter()
+                            .map(|key| key_name(*key))
+                            .collect();
+                        names.join(" ")
+                    };
+                    draw_text(canvas, left + 300, y, &keys, color, 2);
+                }
+                draw_centered(
+                    canvas,
+                    top + 480,
+                    "ENTER: ADD KEY   BACKSPACE: CLEAR   F5: DEFAULTS",
+                    0xAAAAAA,
+                    2,
+                );
+                draw_centered(canvas, top + 520, "ESC: SAVE AND GO BACK", 0xAAAAAA, 2);
+            }
+            Screen::HighScores => {
+                dim(canvas);
+                draw_centered(canvas, top + 60, "HIGH SCORES", 0xFFFF00, 6);
+                draw_mode(canvas, top + 112, menus.mode);
+                draw_score_table(
+                    canvas,
+                    top + 150,
+                    &menus.high_scores,
+                    menus.mode,
+                    10,
+                    menus.highlight,
+                );
+                draw_centered(
+                    canvas,
+                    top + 540,
+                    "LEFT/RIGHT: MODE   ENTER: BACK",
+                    0xAAAAAA,
+                    2,
+                );
+            }
+        }
+    }
+}
+
+// Menus are laid out for an 800x600 screen and kept centred in bigger windows
+const LAYOUT_WIDTH: usize = 800;
+const LAYOUT_HEIGHT: usize = 600;
+
+fn layout_origin(canvas: &Canvas) -> (usize, usize) {
+    (
+        canvas.width.saturating_sub(LAYOUT_WIDTH) / 2,
+        canvas.height.saturating_sub(LAYOUT_HEIGHT) / 2,
+    )
+}
+
+fn draw_centered(canvas: &mut Canvas, y: usize, text: &str, color: u32, scale: usize) {
+    let x: usize = canvas.width.saturating_sub(text_width(text, scale)) / 2;
+    draw_text(canvas, x, y, text, color, scale);
+}
+
+// The selected mode between arrows, to show left and right change it
+fn draw_mode(canvas: &mut Canvas, y: usize, mode: GameMode) {
+    draw_centered(canvas, y, &format!("< {} >", mode.label()), 0xFFDD33, 3);
+}
+
+// One mode's table. Columns: rank, name, score, seconds survived, misses, date and, in small
+// print, the seed.
+fn draw_score_table(
+    canvas: &mut Canvas,
+    y: usize,
+    scores: &HighScores,
+    mode: GameMode,
+    rows: usize,
+    highlight: Option<usize>,
+) {
+    let table: Vec<&HighScore> = scores.table(mode);
+    if !mode.has_high_scores() {
+        draw_centered(canvas, y + 20, "ZEN KEEPS NO SCORE", 0xAAAAAA, 3);
+        return;
+    }
+    if table.is_empty() {
+        draw_centered(canvas, y + 20, "NO SCORES YET", 0xAAAAAA, 3);
+        return;
+    }
+
+    let (left, _) = layout_origin(canvas);
+    let scale: usize = 2;
+    let header_color: u32 = 0x888888;
+    for (label, x) in [
+        ("NAME", 90),
+        ("SCORE", 170),
+        ("TIME", 290),
+        ("MISSED", 390),
+        ("DATE", 500),
+        ("SEED", 660),
+    ] {
+        draw_text(canvas, left + x, y, label, header_color, scale);
+    }
+
+    for (i, entry) in table.into_iter().take(rows).enumerate() {
+        let row_y: usize = y + 30 + i * 30;
+        let color: u32 = if highlight == Some(i) {
+            0x00FF00
+        } else {
+            0xFFFFFF
+        };
+        draw_number(canvas, left + 40, row_y, i + 1, color, scale);
+        draw_text(canvas, left + 90, row_y, &entry.name, color, scale);
+        draw_number(canvas, left + 170, row_y, entry.score, color, scale);
+        draw_number(
+            canvas,
+            left + 290,
+            row_y,
+            entry.seconds as usize,
+            color,
+            scale,
+        );
+        draw_number(canvas, left + 390, row_y, entry.missed, color, scale);
+        draw_text(
+            canvas,
+            left + 500,
+            row_y,
+            &entry.date_string(),
+            color,
+            scale,
+        );
+        draw_number(canvas, left + 660, row_y + 2, entry.seed as usize, color, 1);
+    }
+}
+
+fn draw_stat(canvas: &mut Canvas, x: usize, y: usize, label: &str, value: u64) {
+    draw_text(canvas, x, y, label, 0xDDDDDD, 3);
+    draw_number(canvas, x + 170, y, value as usize, 0xFFFFFF, 3);
+}
+
+// Halve the brightness of everything so text on top stands out
+fn dim(canvas: &mut Canvas) {
+    for pixel in canvas.buffer.iter_mut() {
+        *pixel = (*pixel >> 1) & 0x7F7F7F;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OVER: Status = Status {
+        game_over: true,
+        high_score: false,
+        capturing_key: false,
+    };
+    const HIGH: Status = Status {
+        game_over: true,
+        high_score: true,
+        capturing_key: false,
+    };
+
+    fn press(f: impl FnOnce(&mut MenuInput)) -> MenuInput {
+        let mut menu = MenuInput::default();
+        f(&mut menu);
+        menu
+    }
+
+    #[test]
+    fn test_nothing_pressed_stays() {
+        let idle = MenuInput::default();
+        for screen in [
+            Screen::Title,
+            Screen::Playing,
+            Screen::Paused,
+            Screen::GameOver,
+            Screen::NameEntry,
+            Screen::HighScores,
+            Screen::Controls,
+        ] {
+            assert_eq!(screen.next(&idle, &Status::default()), Transition::Stay);
+        }
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let pause = press(|m| m.pause = true);
+        assert_eq!(
+            Screen::Playing.next(&pause, &Status::default()),
+            Transition::Go(Screen::Paused)
+        );
+        assert_eq!(
+            Screen::Paused.next(&pause, &Status::default()),
+            Transition::Go(Screen::Playing)
+        );
+        assert!(!Screen::Paused.is_running());
+        // A pause is only a break; the game isn't over yet
+        assert!(!Screen::Playing.ends_game(Screen::Paused));
+        assert!(!Screen::Paused.ends_game(Screen::Playing));
+    }
+
+    #[test]
+    fn test_paused_game_can_be_left_for_the_title() {
+        let escape = press(|m| m.back = true);
+        assert_eq!(
+            Screen::Paused.next(&escape, &Status::default()),
+            Transition::Go(Screen::Title)
+        );
+        // Leaving is the end of the game: the main loop saves or reports it like a lost one
+        assert!(Screen::Paused.ends_game(Screen::Title));
+        let scored = Status {
+            high_score: true,
+            ..Status::default()
+        };
+        assert_eq!(
+            Screen::Paused.next(&escape, &scored),
+            Transition::Go(Screen::NameEntry)
+        );
+        assert!(Screen::Paused.ends_game(Screen::NameEntry));
+        // From where a new game, in another mode if wanted, is a press away
+        let enter = press(|m| m.confirm = true);
+        assert_eq!(
+            Screen::Title.next(&enter, &Status::default()),
+            Transition::NewGame
+        );
+    }
+
+    #[test]
+    fn test_game_over_and_restart() {
+        let idle = MenuInput::default();
+        assert_eq!(
+            Screen::Playing.next(&idle, &OVER),
+            Transition::Go(Screen::GameOver)
+        );
+        assert!(Screen::Playing.ends_game(Screen::GameOver));
+        assert!(Screen::Playing.ends_game(Screen::NameEntry));
+        assert!(!Screen::GameOver.ends_game(Screen::HighScores));
+
+        let enter = press(|m| m.confirm = true);
+        assert_eq!(Screen::GameOver.next(&enter, &OVER), Transition::NewGame);
+        assert_eq!(
+            Screen::Title.next(&enter, &Status::default()),
+            Transition::NewGame
+        );
+    }
+
+    #[test]
+    fn test_high_scores_and_back() {
+        let scores = press(|m| m.scores = true);
+        assert_eq!(
+            Screen::Title.next(&scores, &Status::default()),
+            Transition::Go(Screen::HighScores)
+        );
+        assert_eq!(
+            Screen::GameOver.next(&scores, &OVER),
+            Transition::Go(Screen::HighScores)
+        );
+
+        let escape = press(|m| m.back = true);
+        assert_eq!(
+            Screen::HighScores.next(&escape, &Status::default()),
+            Transition::Go(Screen::Title)
+        );
+        assert_eq!(
+            Screen::Title.next(&escape, &Status::default()),
+            Transition::Quit
+        );
+        assert_eq!(
+            Screen::Playing.next(&escape, &Status::default()),
+            Transition::Quit
+        );
+    }
+
+    #[test]
+    fn test_high_score_goes_through_name_entry() {
+        let idle = MenuInput::default();
+        assert_eq!(
+            Screen::Playing.next(&idle, &HIGH),
+            Transition::Go(Screen::NameEntry)
+        );
+
+        // H and P are initials here, not shortcuts
+        let h = press(|m| {
+            m.scores = true;
+            m.letter = Some('H');
+        });
+        assert_eq!(Screen::NameEntry.next(&h, &HIGH), Transition::Stay);
+
+        let enter = press(|m| m.confirm = true);
+        assert_eq!(
+            Screen::NameEntry.next(&enter, &HIGH),
+            Transition::SubmitScore
+        );
+        let escape = press(|m| m.back = true);
+        assert_eq!(
+            Screen::NameEntry.next(&escape, &HIGH),
+            Transition::Go(Screen::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_left_and_right_pick_the_mode() {
+        let mut menus = Menus {
+            highlight: Some(0),
+            ..Menus::default()
+        };
+        menus.pick_mode(&press(|m| m.right = true));
+        assert_eq!(menus.mode, GameMode::TimeAttack);
+        // The highlighted row belonged to the other table
+        assert_eq!(menus.highlight, None);
+        menus.pick_mode(&press(|m| m.left = true));
+        menus.pick_mode(&press(|m| m.left = true));
+        assert_eq!(menus.mode, GameMode::Daily);
+        menus.pick_mode(&MenuInput::default());
+        assert_eq!(menus.mode, GameMode::Daily);
+    }
+
+    #[test]
+    fn test_initials_editing() {
+        let mut initials = Initials::default();
+        initials.edit(&press(|m| m.letter = Some('E')));
+        initials.edit(&press(|m| m.down = true));
+        initials.edit(&press(|m| m.right = true));
+        initials.edit(&press(|m| m.letter = Some('L')));
+        assert_eq!(initials.name(), "EZL");
+        assert_eq!(initials.cursor, 2);
+
+        initials.edit(&press(|m| m.up = true));
+        assert_eq!(initials.name(), "EZM");
+        initials.edit(&press(|m| m.backspace = true));
+        initials.edit(&press(|m| m.up = true));
+        assert_eq!(initials.name(), "EAM");
+    }
+
+    #[test]
+    fn test_controls_screen() {
+        let c = press(|m| m.controls = true);
+        assert_eq!(
+            Screen::Title.next(&c, &Status::default()),
+            Transition::Go(Screen::Controls)
+        );
+
+        // While waiting for a key, even escape belongs to the controls screen
+        let escape = press(|m| {
+            m.back = true;
+            m.key = Some(Key::Escape);
+        });
+        let capturing = Status {
+            capturing_key: true,
+            ..Status::default()
+        };
+        assert_eq!(Screen::Controls.next(&escape, &capturing), Transition::Stay);
+        assert_eq!(
+            Screen::Controls.next(&escape, &Status::default()),
+            Transition::Go(Screen::Title)
+        );
+    }
+
+    #[test]
+    fn test_rebinding() {
+        let mut bindings = Bindings::default();
+        let mut controls = ControlsMenu::default();
+
+        // Select "fire" and give it the J key
+        for _ in 0..4 {
+            controls.edit(&press(|m| m.down = true), &mut bindings);
+        }
+        controls.edit(&press(|m| m.confirm = true), &mut bindings);
+        assert!(controls.capturing);
+        controls.edit(&press(|m| m.key = Some(Key::J)), &mut bindings);
+        assert!(!controls.capturing);
+        assert_eq!(bindings.keys(Action::Fire), &[Key::Space, Key::J]);
+
+        // Escape cancels a capture without binding it
+        controls.edit(&press(|m| m.confirm = true), &mut bindings);
+        controls.edit(&press(|m| m.key = Some(Key::Escape)), &mut bindings);
+        assert_eq!(bindings.keys(Action::Fire), &[Key::Space, Key::J]);
+
+        controls.edit(&press(|m| m.backspace = true), &mut bindings);
+        assert!(bindings.keys(Action::Fire).is_empty());
+        controls.edit(&press(|m| m.key = Some(Key::F5)), &mut bindings);
+        assert_eq!(bindings, Bindings::default());
+    }
+
+    #[test]
+    fn test_screens_fit_narrow_windows() {
+        let game = GameState::new(
+            &crate::settings::Settings::default(),
+            1,
+            crate::game_state::DEFAULT_TICK_RATE,
+            GameMode::Endless,
+        );
+        let menus = Menus::default();
+        let mut canvas = Canvas::new(300, 200);
+        for screen in [
+            Screen::Title,
+            Screen::Playing,
+            Screen::Paused,
+            Screen::GameOver,
+            Screen::NameEntry,
+            Screen::HighScores,
+            Screen::Controls,
+        ] {
+            screen.draw_overlay(&mut canvas, &game, &menus);
+        }
+    }
+}
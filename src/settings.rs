@@ -0,0 +1,267 @@
+use crate::health::MAX_LIVES;
+use crate::paths;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Tunable game settings. The file is a small subset of TOML: `[section]` headers,
+// `key = number` lines and `#` comments, for example
+//   [difficulty]
+//   max_escaped = 15
+// Keys are addressed as "section.key", which is also what --set takes on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    // Play field size in pixels, and the window's starting size
+    pub width: usize,
+    pub height: usize,
+    // Draw the game world this many times bigger for a chunky look; menus stay sharp
+    pub pixel_scale: usize,
+    // Background stars per layer
+    pub stars: usize,
+    // Particles per explosion
+    pub particles: usize,
+    // Missed stars that end the game, whatever shape the ship is in
+    pub max_escaped: usize,
+    // Ships to a game, hull points each, and shield points, which come back between hits
+    pub lives: usize,
+    pub hull: usize,
+    pub shield: usize,
+    // Bombs at the start of a game
+    pub bombs: usize,
+    // How close a star has to be for a bomb to destroy it, in pixels
+    pub bomb_radius: f32,
+    // Seconds between bombs
+    pub bomb_cooldown: f32,
+    // Big stars spawned per second once the levels run out, and how much that grows every second
+    pub spawn_rate: f64,
+    pub spawn_ramp: f64,
+    // Level to start on, counting from 1; past the last one starts straight in endless play
+    pub start_level: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            pixel_scale: 1,
+            stars: 1000,
+            particles: 40,
+            max_escaped: 10,
+            lives: 3,
+            hull: 3,
+            shield: 2,
+            bombs: 3,
+            bomb_radius: 30.0,
+            bomb_cooldown: 0.1,
+            spawn_rate: 0.6,
+            spawn_ramp: 0.0036,
+            start_level: 1,
+        }
+    }
+}
+
+impl Settings {
+    // Sets one "section.key" from its text value; errors name the key
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "window.width" => self.width = whole(key, value, 640, 3840)?,
+            "window.height" => self.height = whole(key, value, 480, 2160)?,
+            "window.pixel_scale" => self.pixel_scale = whole(key, value, 1, 8)?,
+            "effects.stars" => self.stars = whole(key, value, 0, 20_000)?,
+            "effects.particles" => self.particles = whole(key, value, 0, 1000)?,
+            "difficulty.max_escaped" => self.max_escaped = whole(key, value, 1, 1000)?,
+            "difficulty.lives" => self.lives = whole(key, value, 1, MAX_LIVES)?,
+            "difficulty.hull" => self.hull = whole(key, value, 1, 20)?,
+            "difficulty.shield" => self.shield = whole(key, value, 0, 20)?,
+            "difficulty.bombs" => self.bombs = whole(key, value, 0, 99)?,
+            // Also under their names from when the bomb was the only way to fire
+            "difficulty.bomb_radius" | "difficulty.fire_radius" => {
+                self.bomb_radius = number(key, value, 1.0, 500.0)? as f32
+            }
+            "difficulty.bomb_cooldown" | "difficulty.fire_cooldown" => {
+                self.bomb_cooldown = number(key, value, 0.0, 10.0)? as f32
+            }
+            "difficulty.spawn_rate" => self.spawn_rate = number(key, value, 0.01, 100.0)?,
+            "difficulty.spawn_ramp" => self.spawn_ramp = number(key, value, 0.0, 10.0)?,
+            "difficulty.start_level" => self.start_level = whole(key, value, 1, 99)?,
+            _ => return Err(format!("unknown setting '{key}'")),
+        }
+        Ok(())
+    }
+
+    // The same window and effects, with everything under [difficulty] back at its default
+    pub fn with_default_difficulty(&self) -> Settings {
+        Settings {
+            width: self.width,
+            height: self.height,
+            pixel_scale: self.pixel_scale,
+            stars: self.stars,
+            particles: self.particles,
+            ..Settings::default()
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "[window]\n\
+             width = {}\n\
+             height = {}\n\
+             pixel_scale = {}\n\
+             \n\
+             [effects]\n\
+             stars = {}\n\
+             particles = {}\n\
+             \n\
+             [difficulty]\n\
+             max_escaped = {}\n\
+             lives = {}\n\
+             hull = {}\n\
+             shield = {}\n\
+             bombs = {}\n\
+             bomb_radius = {}\n\
+             bomb_cooldown = {}\n\
+             spawn_rate = {}\n\
+             spawn_ramp = {}\n\
+             start_level = {}\n",
+            self.width,
+            self.height,
+            self.pixel_scale,
+            self.stars,
+            self.particles,
+            self.max_escaped,
+            self.lives,
+            self.hull,
+            self.shield,
+            self.bombs,
+            self.bomb_radius,
+            self.bomb_cooldown,
+            self.spawn_rate,
+            self.spawn_ramp,
+            self.start_level
+        )
+    }
+
+    // Starts from the defaults. Every bad line is reported, one per line of the error.
+    pub fn from_text(text: &str) -> Result<Settings, String> {
+        let mut settings: Settings = Settings::default();
+        let mut errors: Vec<String> = Vec::new();
+        let mut section: String = String::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let result: Result<(), String> = match line.split_once('=') {
+                Some((key, value)) => {
+                    let key: String = format!("{section}.{}", key.trim());
+                    settings.set(&key, value.trim())
+                }
+                None => Err("expected 'key = value' or '[section]'".to_string()),
+            };
+            if let Err(message) = result {
+                errors.push(format!("line {}: {message}", number + 1));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    // A missing file means the defaults
+    pub fn load(path: &Path) -> Result<Settings, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Settings::from_text(&text)
+                .map_err(|errors| format!("Bad settings in {}:\n{errors}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(e) => Err(format!("Could not read settings {}: {e}", path.display())),
+        }
+    }
+}
+
+fn whole(key: &str, value: &str, min: usize, max: usize) -> Result<usize, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|n| (min..=max).contains(n))
+        .ok_or(format!(
+            "{key} expects a whole number from {min} to {max}, got '{value}'"
+        ))
+}
+
+fn number(key: &str, value: &str, min: f64, max: f64) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|n| (min..=max).contains(n))
+        .ok_or(format!(
+            "{key} expects a number from {min} to {max}, got '{value}'"
+        ))
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("eagle").join("settings.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let settings = Settings {
+            max_escaped: 15,
+            bombs: 0,
+            spawn_ramp: 0.01,
+            bomb_cooldown: 0.25,
+            start_level: 3,
+            lives: 5,
+            shield: 0,
+            ..Settings::default()
+        };
+        assert_eq!(Settings::from_text(&settings.to_text()), Ok(settings));
+    }
+
+    #[test]
+    fn test_partial_file() {
+        let text =
+            "# easier\n[difficulty]\nmax_escaped = 20   # more forgiving\nbomb_radius = 45\n";
+        let settings = Settings::from_text(text).unwrap();
+        assert_eq!(settings.max_escaped, 20);
+        assert_eq!(settings.bomb_radius, 45.0);
+        assert_eq!(settings.width, 800);
+
+        // Older files and replays still use the old names
+        let old =
+            Settings::from_text("[difficulty]\nfire_radius = 12\nfire_cooldown = 2\n").unwrap();
+        assert_eq!((old.bomb_radius, old.bomb_cooldown), (12.0, 2.0));
+    }
+
+    #[test]
+    fn test_errors_name_the_key() {
+        let text = "[window]\nwidth = wide\n[difficulty]\nmax_escaped = 0\ncontinues = 3\nnonsense";
+        let errors = Settings::from_text(text).unwrap_err();
+        let lines: Vec<&str> = errors.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("line 2: window.width expects"));
+        assert!(lines[1].starts_with("line 4: difficulty.max_escaped expects"));
+        assert_eq!(lines[2], "line 5: unknown setting 'difficulty.continues'");
+        assert!(lines[3].starts_with("line 6:"));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut settings = Settings::default();
+        settings.set("effects.particles", "100").unwrap();
+        assert_eq!(settings.particles, 100);
+        assert!(settings.set("effects.particles", "-1").is_err());
+        assert!(settings.set("particles", "10").is_err());
+    }
+}
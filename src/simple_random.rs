@@ -1,11 +1,24 @@
-use std::time::{SystemTime, UNIX_EPOCH};
 use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+#[derive(Clone, Debug)]
 pub struct SimpleRng(u64);
 
 impl SimpleRng {
-    pub fn new() -> Self {
-        SimpleRng(simple_seed())
+    pub fn new(seed: u64) -> Self {
+        // Scramble the seed so that neighbouring seeds (1, 2, 3...) don't produce similar streams
+        SimpleRng(splitmix64(seed))
+    }
+
+    pub fn from_time() -> Self {
+        SimpleRng::new(time_seed())
+    }
+
+    // Derive an independent stream, e.g. for effects that must not disturb gameplay randomness
+    pub fn fork(&mut self) -> SimpleRng {
+        let high: u64 = self.next() as u64;
+        let low: u64 = self.next() as u64;
+        SimpleRng::new((high << 32) | low)
     }
 
     pub fn next(&mut self) -> u32 {
@@ -34,9 +47,60 @@ impl SimpleRng {
     }
 }
 
-fn simple_seed() -> u64 {
+pub fn time_seed() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_nanos() as u64
 }
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z: u64 = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = SimpleRng::new(42);
+        let mut b = SimpleRng::new(42);
+        for _ in 0..1000 {
+            assert_eq!(a.next(), b.next());
+        }
+    }
+
+    #[test]
+    fn test_neighbouring_seeds_diverge() {
+        let mut a = SimpleRng::new(1);
+        let mut b = SimpleRng::new(2);
+        let same = (0..100).filter(|_| a.next() == b.next()).count();
+        assert_eq!(same, 0);
+    }
+
+    #[test]
+    fn test_fork_is_reproducible_and_independent() {
+        let mut parent_a = SimpleRng::new(7);
+        let mut parent_b = SimpleRng::new(7);
+        let mut fork_a = parent_a.fork();
+        let mut fork_b = parent_b.fork();
+
+        assert_eq!(fork_a.next(), fork_b.next());
+        assert_ne!(fork_a.next(), parent_a.next());
+    }
+
+    #[test]
+    fn test_ranges_stay_in_bounds() {
+        let mut rng = SimpleRng::new(3);
+        for _ in 0..1000 {
+            let f = rng.random_range_f32(-1.5..1.5);
+            assert!((-1.5..=1.5).contains(&f));
+            let u = rng.random_range_u32(50..100);
+            assert!((50..100).contains(&u));
+        }
+    }
+}
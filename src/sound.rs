@@ -1,58 +1,123 @@
+use crate::simple_random::SimpleRng;
 use rodio::{Sink, Source};
-use std::f32::consts::PI;
 use std::time::Duration;
-use crate::simple_random::SimpleRng;
-
-pub struct SineWave {
-    pub freq: f32,
-    pub sample_rate: u32,
-    pub duration_samples: u32,
-    pub t: u32,
-}
 
-impl Iterator for SineWave {
-    type Item = f32;
-    fn next(&mut self) -> Option<f32> {
-        if self.t >= self.duration_samples {
-            return None;
+pub type Waveform = fn(f32) -> f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundEvent {
+    Collision,
+    Missed,
+    Fire,
+    Explosion,
+    // Energy dropped below the warning level
+    LowEnergy,
+    // Energy ran out and the guns locked up
+    Overheat,
+    PowerUp,
+    // A wave was seen off
+    WaveCleared,
+    // The ship was hit and the shield took it, or it got through to the hull
+    ShieldHit,
+    HullHit,
+    // The hull gave out and the ship blew up
+    ShipLost,
+    // The boss motif, in full as it arrives, cut short at each new phase, and resolved at the end
+    BossArrives,
+    BossPhase,
+    BossDefeated,
+}
+
+// Game logic reports what happened; the sink decides how (or whether) it is heard.
+pub trait AudioSink {
+    fn play(&mut self, event: SoundEvent);
+}
+
+impl AudioSink for rodio::OutputStreamHandle {
+    fn play(&mut self, event: SoundEvent) {
+        match event {
+            SoundEvent::Collision => play_pitched_tone(110.0, 0.25, saw_wave, self),
+            SoundEvent::Missed => play_pitched_tone(50.0, 0.25, square_wave, self),
+            SoundEvent::Fire => play_pitched_tone(500.0, 0.35, square_wave, self),
+            SoundEvent::Explosion => play_noise_boom(0.1, self),
+            SoundEvent::LowEnergy => play_combo(
+                &[(880.0, 0.06, square_wave), (660.0, 0.06, square_wave)],
+                self,
+            ),
+            SoundEvent::Overheat => play_pitched_tone(70.0, 0.6, saw_wave, self),
+            SoundEvent::PowerUp => play_combo(
+                &[
+                    (660.0, 0.05, square_wave),
+                    (880.0, 0.05, square_wave),
+                    (1320.0, 0.08, square_wave),
+                ],
+                self,
+            ),
+            SoundEvent::BossArrives => play_combo(
+                &[
+                    (110.0, 0.25, saw_wave),
+                    (104.0, 0.25, saw_wave),
+                    (98.0, 0.25, saw_wave),
+                    (73.0, 0.6, saw_wave),
+                ],
+                self,
+            ),
+            SoundEvent::BossPhase => {
+                play_combo(&[(110.0, 0.15, saw_wave), (73.0, 0.3, saw_wave)], self)
+            }
+            SoundEvent::BossDefeated => play_combo(
+                &[
+                    (110.0, 0.15, saw_wave),
+                    (147.0, 0.15, square_wave),
+                    (220.0, 0.15, square_wave),
+                    (440.0, 0.4, square_wave),
+                ],
+                self,
+            ),
+            SoundEvent::ShieldHit => play_pitched_tone(1200.0, 0.12, saw_wave, self),
+            SoundEvent::HullHit => {
+                play_combo(&[(180.0, 0.08, square_wave), (90.0, 0.15, saw_wave)], self)
+            }
+            SoundEvent::ShipLost => {
+                play_noise_boom(0.4, self);
+                play_combo(
+                    &[
+                        (220.0, 0.2, square_wave),
+                        (165.0, 0.2, square_wave),
+                        (110.0, 0.5, square_wave),
+                    ],
+                    self,
+                );
+            }
+            SoundEvent::WaveCleared => play_combo(
+                &[
+                    (440.0, 0.08, square_wave),
+                    (554.0, 0.08, square_wave),
+                    (659.0, 0.08, square_wave),
+                    (880.0, 0.16, square_wave),
+                ],
+                self,
+            ),
         }
-
-        let progress: f32 = self.t as f32 / self.duration_samples as f32;
-        let envelope: f32 = 1.0 - progress; // linear fade out
-
-        let sample = (2.0 * PI * self.freq * self.t as f32 / self.sample_rate as f32).sin();
-        self.t += 1;
-
-        Some(sample * envelope * 0.3) // slightly louder, but smoothed
     }
 }
 
-impl Source for SineWave {
-    fn current_frame_len(&self) -> Option<usize> {
-        None
-    }
-    fn channels(&self) -> u16 {
-        1
-    }
-    fn sample_rate(&self) -> u32 {
-        self.sample_rate
-    }
-    fn total_duration(&self) -> Option<Duration> {
-        Some(Duration::from_secs_f32(
-            self.duration_samples as f32 / self.sample_rate as f32,
-        ))
-    }
+// Used when there is no audio device (CI, tests, headless runs)
+pub struct NullAudio;
+
+impl AudioSink for NullAudio {
+    fn play(&mut self, _event: SoundEvent) {}
 }
 
 struct NoiseBurst {
     duration_samples: u32,
     t: u32,
+    rng: SimpleRng,
 }
 
 impl Iterator for NoiseBurst {
     type Item = f32;
     fn next(&mut self) -> Option<f32> {
-        let mut rng = SimpleRng::new();
         if self.t >= self.duration_samples {
             return None;
         }
@@ -61,7 +126,7 @@ impl Iterator for NoiseBurst {
         let envelope: f32 = 1.0 - progress;
 
         self.t += 1;
-        let sample = rng.random_range_f32(-1.0..1.0); // white noise [-1.0, 1.0]
+        let sample = self.rng.random_range_f32(-1.0..1.0); // white noise [-1.0, 1.0]
         Some(sample * envelope * 0.4)
     }
 }
@@ -88,7 +153,7 @@ struct PitchedTone {
     sample_rate: u32,
     duration_samples: u32,
     t: u32,
-    waveform: fn(f32) -> f32, // takes phase [0.0..1.0] and returns sample
+    waveform: Waveform, // takes phase [0.0..1.0] and returns sample
 }
 
 impl Iterator for PitchedTone {
@@ -145,7 +210,7 @@ pub fn saw_wave(phase: f32) -> f32 {
 pub fn play_pitched_tone(
     freq: f32,
     duration: f32,
-    waveform: fn(f32) -> f32,
+    waveform: Waveform,
     stream_handle: &rodio::OutputStreamHandle,
 ) {
     if let Ok(sink) = Sink::try_new(stream_handle) {
@@ -166,16 +231,14 @@ pub fn play_noise_boom(duration: f32, stream_handle: &rodio::OutputStreamHandle)
         let burst: NoiseBurst = NoiseBurst {
             duration_samples: (duration * 44100.0) as u32,
             t: 0,
+            rng: SimpleRng::from_time(),
         };
         sink.append(burst);
         sink.detach();
     }
 }
 
-pub fn _play_combo(
-    tones: &[(f32, f32, fn(f32) -> f32)],
-    stream_handle: &rodio::OutputStreamHandle,
-) {
+pub fn play_combo(tones: &[(f32, f32, Waveform)], stream_handle: &rodio::OutputStreamHandle) {
     if let Ok(sink) = Sink::try_new(stream_handle) {
         for &(freq, dur, wave) in tones {
             let tone: PitchedTone = PitchedTone {
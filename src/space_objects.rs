@@ -1,18 +1,260 @@
+use crate::canvas::Canvas;
+use crate::particles::Particle;
+use crate::simple_random::SimpleRng;
+
+// Ship handling: thrust in px/s², drag as the fraction of velocity lost per second,
+// and a top speed so full thrust doesn't run away
+pub const SHIP_ACCELERATION: f32 = 1200.0;
+pub const SHIP_DRAG: f32 = 4.0;
+pub const SHIP_MAX_SPEED: f32 = 240.0;
+
+// Banking: the ship leans up to MAX_BANK radians into sideways motion, easing in at BANK_RATE
+const MAX_BANK: f32 = 0.5;
+const BANK_RATE: f32 = 10.0;
+// Lean past this and the banked sprite is used
+const BANK_THRESHOLD: f32 = 0.15;
+
+const EXHAUST_INTERVAL: f32 = 1.0 / 30.0;
+const EXHAUST_SPEED: f32 = 90.0;
+
+// Ship sprites, nose up: level, banked left, banked right. Each row is 7 bits wide, most
+// significant bit on the left, same as the font in draw_text.
+pub const SHIP_SPRITE_WIDTH: usize = 7;
+pub const SHIP_SPRITES: [[u8; 8]; 3] = [
+    [
+        0b0001000, 0b0001000, 0b0011100, 0b0011100, 0b0111110, 0b1111111, 0b1101011, 0b1000001,
+    ], // level
+    [
+        0b0001000, 0b0001000, 0b0011100, 0b0011100, 0b0111110, 0b0111111, 0b0101011, 0b0000001,
+    ], // banked left
+    [
+        0b0001000, 0b0001000, 0b0011100, 0b0011100, 0b0111110, 0b1111110, 0b1101010, 0b1000000,
+    ], // banked right
+];
+
 pub struct Star {
     pub x: f32,
     pub y: f32,
     pub z: f32,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StarKind {
+    Normal,
+    // Takes several shots to bring down
+    Armored,
+    // Breaks into shards when destroyed
+    Splitter,
+    // A small piece of a splitter
+    Shard,
+    // Comes in three times as fast
+    Streaker,
+    // Slides sideways as it comes in
+    Drifter,
+    // Steers towards the ship
+    Homing,
+}
+
+// Spawn odds out of 100, in order; shards only come from splitters
+const SPAWN_WEIGHTS: [(StarKind, u32); 6] = [
+    (StarKind::Normal, 45),
+    (StarKind::Armored, 12),
+    (StarKind::Splitter, 12),
+    (StarKind::Streaker, 12),
+    (StarKind::Drifter, 12),
+    (StarKind::Homing, 7),
+];
+
+// Sideways speed of drifters and shards, in depth-1 screen halves per second
+const DRIFT_SPEED: f32 = 0.3;
+// Fraction of the way to the ship a homing star closes each second
+const HOMING_TURN: f32 = 0.6;
+
+impl StarKind {
+    // Picks a kind with the odds in SPAWN_WEIGHTS
+    pub fn random(rng: &mut SimpleRng) -> StarKind {
+        let mut roll: u32 = rng.random_range_u32(0..100);
+        for (kind, weight) in SPAWN_WEIGHTS {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        StarKind::Normal
+    }
+
+    // The lowercase name, as written in level files
+    pub fn from_name(name: &str) -> Option<StarKind> {
+        match name {
+            "normal" => Some(StarKind::Normal),
+            "armored" => Some(StarKind::Armored),
+            "splitter" => Some(StarKind::Splitter),
+            "shard" => Some(StarKind::Shard),
+            "streaker" => Some(StarKind::Streaker),
+            "drifter" => Some(StarKind::Drifter),
+            "homing" => Some(StarKind::Homing),
+            _ => None,
+        }
+    }
+
+    // Shots it takes to destroy
+    pub fn toughness(self) -> u8 {
+        match self {
+            StarKind::Armored => 3,
+            _ => 1,
+        }
+    }
+
+    // Scales how fast it comes towards the camera
+    fn speed(self) -> f32 {
+        match self {
+            StarKind::Armored => 0.7,
+            StarKind::Streaker => 3.0,
+            StarKind::Shard => 1.2,
+            _ => 1.0,
+        }
+    }
+
+    // Scales its drawn and hit size
+    fn size(self) -> f32 {
+        match self {
+            StarKind::Armored => 1.3,
+            StarKind::Shard => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    // Scales the points for destroying it
+    pub fn worth(self) -> f32 {
+        match self {
+            StarKind::Armored => 3.0,
+            StarKind::Splitter | StarKind::Drifter => 1.5,
+            StarKind::Streaker => 2.0,
+            StarKind::Shard => 0.5,
+            StarKind::Normal | StarKind::Homing => 1.0,
+        }
+    }
+
+    // Damage it does to the ship by flying into it; the harmless ones are caught instead
+    pub fn contact_damage(self) -> usize {
+        match self {
+            StarKind::Normal | StarKind::Drifter => 0,
+            StarKind::Armored => 2,
+            _ => 1,
+        }
+    }
+
+    // Added to the missed count when it gets away
+    pub fn miss_penalty(self) -> usize {
+        match self {
+            StarKind::Armored | StarKind::Homing => 2,
+            StarKind::Shard => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct BigStar {
     pub x: f32,
     pub y: f32,
     pub z: f32,
+    pub kind: StarKind,
+    // Shots still needed to destroy it
+    pub hits_left: u8,
+    // Sideways velocity in the same units as x and y, per second
+    pub vx: f32,
+    pub vy: f32,
     pub hit: bool,
     pub was_missed: bool,
+    // Already scored for passing close to the ship
+    pub grazed: bool,
 }
 
 impl BigStar {
+    pub fn new(kind: StarKind, x: f32, y: f32, z: f32) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            kind,
+            hits_left: kind.toughness(),
+            vx: 0.0,
+            vy: 0.0,
+            hit: false,
+            was_missed: false,
+            grazed: false,
+        }
+    }
+
+    // A new star of a random kind somewhere in the distance
+    pub fn spawn(rng: &mut SimpleRng) -> Self {
+        let x: f32 = rng.random_range_f32(-1.5..1.5);
+        let y: f32 = rng.random_range_f32(-1.5..1.5);
+        let z: f32 = 2.5 + rng.random_range_f32(0.0..1.0);
+        let kind: StarKind = StarKind::random(rng);
+        BigStar::launch(kind, x, y, z, rng)
+    }
+
+    // A new star of `kind` at (x, y, z), set moving the way its kind moves
+    pub fn launch(kind: StarKind, x: f32, y: f32, z: f32, rng: &mut SimpleRng) -> Self {
+        let mut star: BigStar = BigStar::new(kind, x, y, z);
+        if kind == StarKind::Drifter {
+            star.vx = rng.random_range_f32(-DRIFT_SPEED..DRIFT_SPEED);
+            star.vy = rng.random_range_f32(-DRIFT_SPEED..DRIFT_SPEED);
+        }
+        star
+    }
+
+    // The pieces a splitter leaves, flying apart from where it was
+    pub fn shards(&self, rng: &mut SimpleRng) -> Vec<BigStar> {
+        (0..3)
+            .map(|i| {
+                let angle: f32 =
+                    i as f32 * std::f32::consts::TAU / 3.0 + rng.random_range_f32(0.0..1.0);
+                let mut shard: BigStar = BigStar::new(StarKind::Shard, self.x, self.y, self.z);
+                shard.vx = angle.cos() * DRIFT_SPEED;
+                shard.vy = angle.sin() * DRIFT_SPEED;
+                shard
+            })
+            .collect()
+    }
+
+    // One tick of movement. `approach` is the depth it would close at its kind's normal speed,
+    // and (target_x, target_y) is the ship as a point at depth 1, for homing stars.
+    pub fn advance(&mut self, approach: f32, target_x: f32, target_y: f32, dt: f32) {
+        self.z -= approach * self.kind.speed();
+        self.x += self.vx * dt;
+        self.y += self.vy * dt;
+        if self.kind == StarKind::Homing {
+            let turn: f32 = (HOMING_TURN * dt).min(1.0);
+            self.x += (target_x * self.z - self.x) * turn;
+            self.y += (target_y * self.z - self.y) * turn;
+        }
+    }
+
+    // Takes `damage` shots' worth off it; true if that destroyed it
+    pub fn damage(&mut self, damage: u8) -> bool {
+        self.hits_left = self.hits_left.saturating_sub(damage);
+        if self.hits_left == 0 {
+            self.hit = true;
+        }
+        self.hit
+    }
+
+    // Where the star appears on a width x height screen
+    pub fn project(&self, width: usize, height: usize) -> (f32, f32) {
+        (
+            self.x / self.z * width as f32 / 2.0 + width as f32 / 2.0,
+            self.y / self.z * height as f32 / 2.0 + height as f32 / 2.0,
+        )
+    }
+
+    // Drawn radius in play-field pixels; nearer stars are bigger
+    pub fn radius(&self) -> f32 {
+        ((1.0 / self.z) * 10.0).clamp(1.0, 6.0) * self.kind.size()
+    }
+
     pub fn is_off_screen(&self, width: usize, height: usize) -> bool {
         // Project 3D coordinates to 2D screen space
         let sx: f32 = self.x / self.z * width as f32 / 2.0 + width as f32 / 2.0;
@@ -22,7 +264,160 @@ impl BigStar {
     }
 }
 
+// Axis-aligned box centred on (x, y)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hitbox {
+    pub x: f32,
+    pub y: f32,
+    pub half_width: f32,
+    pub half_height: f32,
+}
+
+impl Hitbox {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        (x - self.x).abs() <= self.half_width && (y - self.y).abs() <= self.half_height
+    }
+}
+
+pub struct Ship {
+    pub x: f32,
+    pub y: f32,
+    // Position at the previous tick, for interpolated drawing
+    pub prev_x: f32,
+    pub prev_y: f32,
+    // Velocity in px/s
+    pub vx: f32,
+    pub vy: f32,
+    // Bank angle in radians, negative leaning left
+    pub heading: f32,
+    // Collision box size; a little inside the sprite so grazes don't count
+    pub half_width: f32,
+    pub half_height: f32,
+    // Seconds until the engine puffs out the next exhaust particle
+    exhaust_timer: f32,
+}
+
+impl Ship {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vx: 0.0,
+            vy: 0.0,
+            heading: 0.0,
+            half_width: 3.0,
+            half_height: 3.5,
+            exhaust_timer: 0.0,
+        }
+    }
+
+    pub fn hitbox(&self) -> Hitbox {
+        Hitbox {
+            x: self.x,
+            y: self.y,
+            half_width: self.half_width,
+            half_height: self.half_height,
+        }
+    }
+
+    // Position between the last two ticks; alpha is how far into the next tick we are
+    pub fn interpolated(&self, alpha: f32) -> (f32, f32) {
+        (
+            self.prev_x + (self.x - self.prev_x) * alpha,
+            self.prev_y + (self.y - self.prev_y) * alpha,
+        )
+    }
+
+    // Thrust along `axis`, bleed off speed through drag, then move. Hitting an edge stops
+    // motion into it so the ship doesn't stick there.
+    pub fn steer(&mut self, axis: (f32, f32), dt: f32, width: usize, height: usize) {
+        self.vx += axis.0 * SHIP_ACCELERATION * dt;
+        self.vy += axis.1 * SHIP_ACCELERATION * dt;
+
+        let drag: f32 = (1.0 - SHIP_DRAG * dt).max(0.0);
+        self.vx *= drag;
+        self.vy *= drag;
+
+        let speed: f32 = (self.vx * self.vx + self.vy * self.vy).sqrt();
+        if speed > SHIP_MAX_SPEED {
+            self.vx *= SHIP_MAX_SPEED / speed;
+            self.vy *= SHIP_MAX_SPEED / speed;
+        }
+
+        self.x += self.vx * dt;
+        self.y += self.vy * dt;
+
+        let max_x: f32 = width as f32 - 1.0;
+        let max_y: f32 = height as f32 - 1.0;
+        if self.x <= 0.0 || self.x >= max_x {
+            self.x = self.x.clamp(0.0, max_x);
+            self.vx = 0.0;
+        }
+        if self.y <= 0.0 || self.y >= max_y {
+            self.y = self.y.clamp(0.0, max_y);
+            self.vy = 0.0;
+        }
+
+        let bank: f32 = self.vx / SHIP_MAX_SPEED * MAX_BANK;
+        self.heading += (bank - self.heading) * (BANK_RATE * dt).min(1.0);
+    }
+
+    // While thrusting, puffs a short-lived particle out the opposite way every EXHAUST_INTERVAL
+    pub fn exhaust(&mut self, axis: (f32, f32), dt: f32, rng: &mut SimpleRng) -> Option<Particle> {
+        self.exhaust_timer -= dt;
+        if axis == (0.0, 0.0) || self.exhaust_timer > 0.0 {
+            return None;
+        }
+        self.exhaust_timer = EXHAUST_INTERVAL;
+
+        let life: f32 = rng.random_range_f32(0.15..0.3);
+        Some(Particle {
+            x: self.x - axis.0 * 4.0,
+            y: self.y - axis.1 * 4.0,
+            vx: self.vx * 0.5 - axis.0 * EXHAUST_SPEED + rng.random_range_f32(-15.0..15.0),
+            vy: self.vy * 0.5 - axis.1 * EXHAUST_SPEED + rng.random_range_f32(-15.0..15.0),
+            life,
+            initial_life: life,
+        })
+    }
+
+    pub fn sprite(&self) -> &'static [u8; 8] {
+        if self.heading < -BANK_THRESHOLD {
+            &SHIP_SPRITES[1]
+        } else if self.heading > BANK_THRESHOLD {
+            &SHIP_SPRITES[2]
+        } else {
+            &SHIP_SPRITES[0]
+        }
+    }
+
+    // Draws the sprite centred on (x, y), every sprite pixel as a scale x scale block
+    pub fn draw(&self, canvas: &mut Canvas, x: isize, y: isize, color: u32, scale: usize) {
+        let sprite: &[u8; 8] = self.sprite();
+        let block: isize = scale as isize;
+        let left: isize = x - (SHIP_SPRITE_WIDTH / 2) as isize * block;
+        let top: isize = y - (sprite.len() / 2) as isize * block;
+        for (dy, row) in sprite.iter().enumerate() {
+            for dx in 0..SHIP_SPRITE_WIDTH {
+                if (row >> (SHIP_SPRITE_WIDTH - 1 - dx)) & 1 == 1 {
+                    canvas.fill_rect(
+                        left + dx as isize * block,
+                        top + dy as isize * block,
+                        scale,
+                        scale,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
+// The tests are kept as first written; clippy would have them spelled differently
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -30,13 +425,7 @@ mod tests {
     fn test_star_center_on_screen() {
         let width = 800;
         let height = 600;
-        let star = BigStar {
-            x: 0.0,
-            y: 0.0,
-            z: 1.0,
-            hit: false,
-            was_missed: false,
-        };
+        let star = BigStar::new(StarKind::Normal, 0.0, 0.0, 1.0);
         assert_eq!(star.is_off_screen(width, height), false);
     }
 
@@ -44,13 +433,7 @@ mod tests {
     fn test_star_far_left_off_screen() {
         let width = 800;
         let height = 600;
-        let star = BigStar {
-            x: -20.0,
-            y: 0.0,
-            z: 10.0,
-            hit: false,
-            was_missed: false,
-        };
+        let star = BigStar::new(StarKind::Normal, -20.0, 0.0, 10.0);
         assert_eq!(star.is_off_screen(width, height), true);
     }
 
@@ -58,13 +441,7 @@ mod tests {
     fn test_star_far_right_off_screen() {
         let width = 800;
         let height = 600;
-        let star = BigStar {
-            x: 10.0,
-            y: 0.0,
-            z: 1.0,
-            hit: false,
-            was_missed: false,
-        };
+        let star = BigStar::new(StarKind::Normal, 10.0, 0.0, 1.0);
         assert_eq!(star.is_off_screen(width, height), true);
     }
 
@@ -72,13 +449,96 @@ mod tests {
     fn test_star_below_screen() {
         let width = 800;
         let height = 600;
-        let star = BigStar {
-            x: 0.0,
-            y: 10.0,
-            z: 1.0,
-            hit: false,
-            was_missed: false,
-        };
+        let star = BigStar::new(StarKind::Normal, 0.0, 10.0, 1.0);
         assert_eq!(star.is_off_screen(width, height), true);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_hitbox() {
+        let ship = Ship::new(100.0, 50.0);
+        let hitbox = ship.hitbox();
+        assert!(hitbox.contains(100.0, 50.0));
+        assert!(hitbox.contains(103.0, 46.5));
+        assert!(!hitbox.contains(104.0, 50.0));
+        assert!(!hitbox.contains(100.0, 54.0));
+    }
+
+    #[test]
+    fn test_interpolated() {
+        let mut ship = Ship::new(100.0, 50.0);
+        ship.x = 110.0;
+        ship.y = 40.0;
+
+        assert_eq!(ship.interpolated(0.0), (100.0, 50.0));
+        assert_eq!(ship.interpolated(0.5), (105.0, 45.0));
+        assert_eq!(ship.interpolated(1.0), (110.0, 40.0));
+    }
+
+    #[test]
+    fn test_banks_into_turns() {
+        let mut ship = Ship::new(400.0, 300.0);
+        assert_eq!(ship.sprite(), &SHIP_SPRITES[0]);
+        for _ in 0..30 {
+            ship.steer((-1.0, 0.0), 1.0 / 60.0, 800, 600);
+        }
+        assert!(ship.heading < 0.0);
+        assert_eq!(ship.sprite(), &SHIP_SPRITES[1]);
+
+        // Levels out again once it stops
+        for _ in 0..300 {
+            ship.steer((0.0, 0.0), 1.0 / 60.0, 800, 600);
+        }
+        assert_eq!(ship.sprite(), &SHIP_SPRITES[0]);
+    }
+
+    #[test]
+    fn test_exhaust_only_while_thrusting() {
+        let mut ship = Ship::new(400.0, 300.0);
+        let mut rng = SimpleRng::new(1);
+        assert!(ship.exhaust((0.0, 0.0), 1.0 / 60.0, &mut rng).is_none());
+
+        let puff = ship.exhaust((0.0, -1.0), 1.0 / 60.0, &mut rng).unwrap();
+        // Thrusting up blows exhaust down, out of the tail
+        assert!(puff.y > ship.y && puff.vy > 0.0);
+        // Not again until the interval has passed
+        assert!(ship.exhaust((0.0, -1.0), 1.0 / 60.0, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_armored_takes_several_hits() {
+        let mut star = BigStar::new(StarKind::Armored, 0.0, 0.0, 2.0);
+        assert!(!star.damage(1));
+        assert!(!star.damage(1));
+        assert!(star.damage(1));
+        // A beam does it in one
+        let mut star = BigStar::new(StarKind::Armored, 0.0, 0.0, 2.0);
+        assert!(star.damage(3));
+    }
+
+    #[test]
+    fn test_kinds_move_differently() {
+        let mut normal = BigStar::new(StarKind::Normal, 0.5, 0.5, 2.0);
+        let mut streaker = BigStar::new(StarKind::Streaker, 0.5, 0.5, 2.0);
+        let mut homing = BigStar::new(StarKind::Homing, 0.5, 0.5, 2.0);
+        for star in [&mut normal, &mut streaker, &mut homing] {
+            star.advance(0.01, -0.5, -0.5, 0.1);
+        }
+        assert!((normal.z - 1.99).abs() < 1e-6);
+        assert!((streaker.z - 1.97).abs() < 1e-6);
+        assert_eq!((normal.x, normal.y), (0.5, 0.5));
+        assert!(homing.x < 0.5 && homing.y < 0.5);
+    }
+
+    #[test]
+    fn test_splitter_breaks_into_shards() {
+        let splitter = BigStar::new(StarKind::Splitter, 0.2, -0.1, 1.5);
+        let shards = splitter.shards(&mut SimpleRng::new(4));
+        assert_eq!(shards.len(), 3);
+        for shard in &shards {
+            assert_eq!(shard.kind, StarKind::Shard);
+            assert_eq!((shard.x, shard.y, shard.z), (0.2, -0.1, 1.5));
+            assert!(shard.radius() < splitter.radius());
+        }
+        assert!(shards[0].vx != shards[1].vx);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::space_objects::Ship;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weapon {
+    // Quick single shots while fire is held
+    Pulse,
+    // A fan of short-lived pellets
+    Spread,
+    // Hold fire to charge, let go to fire a beam that passes through everything
+    Beam,
+}
+
+pub const WEAPONS: [Weapon; 3] = [Weapon::Pulse, Weapon::Spread, Weapon::Beam];
+
+// Seconds of holding fire for a full-power beam, and the least charge that fires at all
+pub const FULL_CHARGE: f32 = 1.0;
+const MIN_CHARGE: f32 = 0.2;
+
+// Firing drains energy, which refills over time. Emptying it overheats the guns, and they stay
+// locked until the energy is back up to COOLED_DOWN. Dropping below LOW_ENERGY warns once, and
+// again only after refilling that far.
+pub const MAX_ENERGY: f32 = 100.0;
+const ENERGY_REGEN: f32 = 20.0;
+const LOW_ENERGY: f32 = 25.0;
+const COOLED_DOWN: f32 = 50.0;
+// A beam costs the base plus up to the full extra at full charge
+const BEAM_COST: f32 = 10.0;
+const FULL_BEAM_COST: f32 = 30.0;
+
+// Directions are angles from straight up, in radians, positive to the right
+const SPREAD_ANGLES: [f32; 5] = [-0.4, -0.2, 0.0, 0.2, 0.4];
+
+impl Weapon {
+    pub fn label(self) -> &'static str {
+        match self {
+            Weapon::Pulse => "PULSE",
+            Weapon::Spread => "SPREAD",
+            Weapon::Beam => "BEAM",
+        }
+    }
+
+    // Seconds before the weapon can fire again
+    fn cooldown(self) -> f32 {
+        match self {
+            Weapon::Pulse => 0.15,
+            Weapon::Spread => 0.4,
+            Weapon::Beam => 0.3,
+        }
+    }
+
+    // Energy per shot (or per volley) at `power` from 0 to 1, which only the beam uses
+    fn cost(self, power: f32) -> f32 {
+        match self {
+            Weapon::Pulse => 8.0,
+            Weapon::Spread => 20.0,
+            Weapon::Beam => BEAM_COST + FULL_BEAM_COST * power,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Energy {
+    pub level: f32,
+    pub overheated: bool,
+    low: bool,
+}
+
+impl Default for Energy {
+    fn default() -> Self {
+        Self {
+            level: MAX_ENERGY,
+            overheated: false,
+            low: false,
+        }
+    }
+}
+
+impl Energy {
+    pub fn regen(&mut self, dt: f32) {
+        self.level = (self.level + ENERGY_REGEN * dt).min(MAX_ENERGY);
+        if self.level >= COOLED_DOWN {
+            self.overheated = false;
+            self.low = false;
+        }
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.low
+    }
+
+    // A shot can always be paid for while not overheated; overdrawing is what overheats
+    fn drain(&mut self, cost: f32) {
+        self.level -= cost;
+        if self.level < LOW_ENERGY {
+            self.low = true;
+        }
+        if self.level <= 0.0 {
+            self.level = 0.0;
+            self.overheated = true;
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projectile {
+    pub x: f32,
+    pub y: f32,
+    // Velocity in pixels per second
+    pub vx: f32,
+    pub vy: f32,
+    // Seconds left to live
+    pub life: f32,
+    // Anything within this many pixels (plus its own size) is hit
+    pub radius: f32,
+    // Piercing shots keep going after a hit
+    pub piercing: bool,
+    // Shots' worth of damage it does to what it hits
+    pub damage: u8,
+    pub weapon: Weapon,
+}
+
+impl Projectile {
+    fn new(ship: &Ship, weapon: Weapon, angle: f32, speed: f32, life: f32, radius: f32) -> Self {
+        Self {
+            x: ship.x,
+            y: ship.y - 5.0,
+            vx: angle.sin() * speed,
+            vy: -angle.cos() * speed,
+            life,
+            radius,
+            piercing: weapon == Weapon::Beam,
+            damage: if weapon == Weapon::Beam { 3 } else { 1 },
+            weapon,
+        }
+    }
+}
+
+pub struct Armory {
+    pub selected: Weapon,
+    // Seconds until each weapon, indexed like WEAPONS, can fire again
+    cooldowns: [f32; WEAPONS.len()],
+    // Seconds the beam has been charging
+    pub charge: f32,
+    pub bombs: usize,
+    bomb_cooldown: f32,
+    // Button states on the previous tick, so switching and bombing happen once per press
+    switch_held: bool,
+    bomb_held: bool,
+}
+
+impl Armory {
+    pub fn new(bombs: usize) -> Self {
+        Self {
+            selected: Weapon::Pulse,
+            cooldowns: [0.0; WEAPONS.len()],
+            charge: 0.0,
+            bombs,
+            bomb_cooldown: 0.0,
+            switch_held: false,
+            bomb_held: false,
+        }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        for cooldown in self.cooldowns.iter_mut() {
+            *cooldown = (*cooldown - dt).max(0.0);
+        }
+        self.bomb_cooldown = (self.bomb_cooldown - dt).max(0.0);
+    }
+
+    // Moves on to the next weapon when the button goes down; an unfinished charge is lost
+    pub fn cycle(&mut self, held: bool) {
+        if held && !self.switch_held {
+            let index: usize = self.selected as usize;
+            self.selected = WEAPONS[(index + 1) % WEAPONS.len()];
+            self.charge = 0.0;
+        }
+        self.switch_held = held;
+    }
+
+    // Runs the selected weapon for one tick with the fire button `held`, paying for any new
+    // shots out of `energy`
+    pub fn fire(
+        &mut self,
+        held: bool,
+        ship: &Ship,
+        energy: &mut Energy,
+        dt: f32,
+    ) -> Vec<Projectile> {
+        let weapon: Weapon = self.selected;
+        let ready: bool = self.cooldowns[weapon as usize] <= 0.0 && !energy.overheated;
+        let mut power: f32 = 0.0;
+        // Shots lean with the ship
+        let aim: f32 = ship.heading;
+
+        let shots: Vec<Projectile> = match weapon {
+            Weapon::Pulse if held && ready => {
+                vec![Projectile::new(ship, weapon, aim, 600.0, 1.0, 3.0)]
+            }
+            Weapon::Spread if held && ready => SPREAD_ANGLES
+                .iter()
+                .map(|angle| Projectile::new(ship, weapon, aim + angle, 450.0, 0.6, 2.0))
+                .collect(),
+            Weapon::Beam if held => {
+                self.charge = (self.charge + dt).min(FULL_CHARGE);
+                Vec::new()
+            }
+            // A charge let go of while the guns aren't ready is kept, and goes off once they are
+            Weapon::Beam if self.charge >= MIN_CHARGE && ready => {
+                power = self.charge / FULL_CHARGE;
+                self.charge = 0.0;
+                vec![Projectile::new(
+                    ship,
+                    weapon,
+                    aim,
+                    1200.0,
+                    0.5,
+                    4.0 + 8.0 * power,
+                )]
+            }
+            Weapon::Beam => {
+                if self.charge < MIN_CHARGE {
+                    self.charge = 0.0;
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+
+        if !shots.is_empty() {
+            self.cooldowns[weapon as usize] = weapon.cooldown();
+            energy.drain(weapon.cost(power));
+        }
+        shots
+    }
+
+    // True when a press of the bomb button sets one off; `cooldown` is the wait before the next
+    pub fn bomb(&mut self, held: bool, cooldown: f32) -> bool {
+        let pressed: bool = held && !self.bomb_held;
+        self.bomb_held = held;
+        if !pressed || self.bombs == 0 || self.bomb_cooldown > 0.0 {
+            return false;
+        }
+        self.bombs -= 1;
+        self.bomb_cooldown = cooldown;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    fn ticks(armory: &mut Armory, ship: &Ship, held: bool, count: usize) -> usize {
+        let mut shots: usize = 0;
+        for _ in 0..count {
+            armory.tick(DT);
+            shots += armory.fire(held, ship, &mut Energy::default(), DT).len();
+        }
+        shots
+    }
+
+    #[test]
+    fn test_pulse_respects_cooldown() {
+        let ship = Ship::new(100.0, 100.0);
+        let mut armory = Armory::new(0);
+        // 0.15s between shots, which rounds up to 10 ticks: one second of holding fire gets 6
+        assert_eq!(ticks(&mut armory, &ship, true, 60), 6);
+
+        let shot = &armory.fire(true, &ship, &mut Energy::default(), DT);
+        assert!(shot.is_empty());
+        armory.tick(1.0);
+        let shot = &armory.fire(true, &ship, &mut Energy::default(), DT)[0];
+        assert!(shot.vy < 0.0 && shot.vx.abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_spread_fans_out() {
+        let ship = Ship::new(100.0, 100.0);
+        let mut armory = Armory::new(0);
+        armory.cycle(true);
+        assert_eq!(armory.selected, Weapon::Spread);
+
+        let shots = armory.fire(true, &ship, &mut Energy::default(), DT);
+        assert_eq!(shots.len(), 5);
+        assert!(shots[0].vx < 0.0 && shots[4].vx > 0.0);
+        assert!(shots.iter().all(|s| s.vy < 0.0 && !s.piercing));
+    }
+
+    #[test]
+    fn test_beam_charges_and_fires_on_release() {
+        let ship = Ship::new(100.0, 100.0);
+        let mut armory = Armory::new(0);
+        armory.cycle(true);
+        armory.cycle(false);
+        armory.cycle(true);
+        assert_eq!(armory.selected, Weapon::Beam);
+
+        // A tap doesn't charge enough
+        assert_eq!(ticks(&mut armory, &ship, true, 5), 0);
+        assert_eq!(ticks(&mut armory, &ship, false, 1), 0);
+
+        assert_eq!(ticks(&mut armory, &ship, true, 120), 0);
+        assert_eq!(armory.charge, FULL_CHARGE);
+        let beam = armory.fire(false, &ship, &mut Energy::default(), DT);
+        assert_eq!(beam.len(), 1);
+        assert!(beam[0].piercing);
+        assert_eq!(beam[0].radius, 12.0);
+        assert_eq!(armory.charge, 0.0);
+    }
+
+    #[test]
+    fn test_beam_keeps_its_charge_until_it_can_fire() {
+        let ship = Ship::new(100.0, 100.0);
+        let mut armory = Armory::new(0);
+        armory.selected = Weapon::Beam;
+        let mut energy = Energy {
+            level: 0.0,
+            overheated: true,
+            low: true,
+        };
+        for _ in 0..70 {
+            armory.fire(true, &ship, &mut energy, DT);
+        }
+        assert!(armory.fire(false, &ship, &mut energy, DT).is_empty());
+        assert_eq!(armory.charge, FULL_CHARGE);
+
+        energy.regen(2.5);
+        let beam = armory.fire(false, &ship, &mut energy, DT);
+        assert_eq!(beam.len(), 1);
+        assert_eq!(beam[0].radius, 12.0);
+        assert_eq!(armory.charge, 0.0);
+    }
+
+    #[test]
+    fn test_overheating_locks_until_cooled() {
+        let ship = Ship::new(100.0, 100.0);
+        let mut armory = Armory::new(0);
+        let mut energy = Energy::default();
+
+        let mut shots: usize = 0;
+        while !energy.overheated {
+            armory.tick(1.0);
+            shots += armory.fire(true, &ship, &mut energy, DT).len();
+        }
+        assert_eq!(shots, 13);
+        assert_eq!(energy.level, 0.0);
+
+        // Locked out even once the cooldown is over
+        armory.tick(1.0);
+        assert!(armory.fire(true, &ship, &mut energy, DT).is_empty());
+
+        energy.regen(2.0);
+        assert!(energy.overheated);
+        energy.regen(0.5);
+        assert!(!energy.overheated && !energy.is_low());
+        assert_eq!(armory.fire(true, &ship, &mut energy, DT).len(), 1);
+    }
+
+    #[test]
+    fn test_bombs_run_out() {
+        let mut armory = Armory::new(2);
+        assert!(armory.bomb(true, 0.1));
+        // Holding the button doesn't set off another
+        assert!(!armory.bomb(true, 0.1));
+        assert!(!armory.bomb(false, 0.1));
+        // Still cooling down
+        assert!(!armory.bomb(true, 0.1));
+        armory.bomb(false, 0.1);
+        armory.tick(0.2);
+        assert!(armory.bomb(true, 0.1));
+        armory.bomb(false, 0.1);
+        armory.tick(0.2);
+        assert!(!armory.bomb(true, 0.1));
+        assert_eq!(armory.bombs, 0);
+    }
+}